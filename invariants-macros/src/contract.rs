@@ -0,0 +1,263 @@
+use proc_macro2::{Delimiter, Group, Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, ItemFn, Token};
+
+use crate::level::Level;
+use crate::wrap;
+
+/// Whether a contract is checked before or after the body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Requires,
+    Ensures,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Requires => "precondition",
+            Kind::Ensures => "postcondition",
+        }
+    }
+}
+
+/// The arguments of `#[requires(level = debug, config = CONFIG, cond)]`.
+struct ContractArgs {
+    level: Level,
+    config: Option<Expr>,
+    condition: TokenStream,
+}
+
+impl Parse for ContractArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut level = Level::Debug;
+        let mut config = None;
+        while input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let value: Expr = input.parse()?;
+            match name.to_string().as_str() {
+                "level" => level = Level::from_expr(&value)?,
+                "config" => config = Some(value),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        "expected `level` or `config`",
+                    ))
+                }
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let condition: TokenStream = input.parse()?;
+        if condition.is_empty() {
+            return Err(input.error("missing condition"));
+        }
+        Ok(ContractArgs {
+            level,
+            config,
+            condition,
+        })
+    }
+}
+
+/// Rewrites `a -> b` into `!(a) || (b)`, including inside parentheses. The `->` of a closure's
+/// return type, right after its parameters, is left alone.
+fn implications(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                let mut new = Group::new(group.delimiter(), implications(group.stream()));
+                new.set_span(group.span());
+                TokenTree::Group(new)
+            }
+            token => token,
+        })
+        .collect();
+    let arrow = (0..tokens.len()).find(|&index| match &tokens[index..] {
+        [TokenTree::Punct(minus), TokenTree::Punct(gt), ..] => {
+            let after_params = matches!(
+                index.checked_sub(1).map(|prev| &tokens[prev]),
+                Some(TokenTree::Punct(bar)) if bar.as_char() == '|'
+            );
+            minus.as_char() == '-'
+                && minus.spacing() == Spacing::Joint
+                && gt.as_char() == '>'
+                && !after_params
+        }
+        _ => false,
+    });
+    match arrow {
+        Some(index) => {
+            let lhs: TokenStream = tokens[..index].iter().cloned().collect();
+            let rhs = implications(tokens[index + 2..].iter().cloned().collect());
+            quote!(!(#lhs) || (#rhs))
+        }
+        None => tokens.into_iter().collect(),
+    }
+}
+
+/// The name of the binding holding the values captured by `old(expr)` on entry.
+const OLD: &str = "__invariants_old";
+
+/// The name of the call site of a postcondition with old values, which also gates their capture.
+const CALLSITE: &str = "__INVARIANTS_POSTCONDITION";
+
+/// Replaces every `old(expr)` with a binding named after its index in `olds`, and pushes `expr`
+/// to `olds`.
+fn take_old(tokens: TokenStream, olds: &mut Vec<TokenStream>) -> TokenStream {
+    let mut out = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        // `x.old(..)` and `path::old(..)` are calls of something else.
+        let after_path = matches!(
+            out.last(),
+            Some(TokenTree::Punct(punct)) if matches!(punct.as_char(), '.' | ':')
+        );
+        match token {
+            TokenTree::Ident(ident) if ident == "old" && !after_path => match tokens.peek() {
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                    let name = format!("{}_{}", OLD, olds.len());
+                    olds.push(group.stream());
+                    tokens.next();
+                    out.push(TokenTree::Ident(Ident::new(&name, Span::call_site())));
+                }
+                _ => out.push(TokenTree::Ident(ident)),
+            },
+            TokenTree::Group(group) => {
+                let mut new = Group::new(group.delimiter(), take_old(group.stream(), olds));
+                new.set_span(group.span());
+                out.push(TokenTree::Group(new));
+            }
+            token => out.push(token),
+        }
+    }
+    out.into_iter().collect()
+}
+
+pub(crate) fn expand(kind: Kind, args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args: ContractArgs = syn::parse2(args)?;
+    let mut item: ItemFn = syn::parse2(item)?;
+    if item.sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            item.sig.asyncness,
+            "contracts are not supported on async functions",
+        ));
+    }
+    if item.sig.constness.is_some() {
+        return Err(syn::Error::new_spanned(
+            item.sig.constness,
+            "contracts are not supported on const functions",
+        ));
+    }
+    let assert = args.level.macro_path("assert");
+    let text = wrap::source_text(&args.condition);
+    let mut olds = Vec::new();
+    let condition = implications(take_old(args.condition, &mut olds));
+    if kind == Kind::Requires && !olds.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "`old` can only be used in postconditions",
+        ));
+    }
+    let message = format!(
+        "{} `{}` of `{}` violated",
+        kind.name(),
+        text,
+        wrap::fn_name(&item.sig)
+    );
+    let check = match &args.config {
+        Some(config) => quote!(#assert!(#config; #condition, "{}", #message);),
+        None => quote!(#assert!(#condition, "{}", #message);),
+    };
+    item.block = Box::new(match kind {
+        Kind::Requires => {
+            wrap::wrap_body(&item.sig, &item.block, wrap::RET, check, TokenStream::new())
+        }
+        // Postconditions refer to the return value as `ret`.
+        Kind::Ensures if olds.is_empty() => {
+            wrap::wrap_body(&item.sig, &item.block, "ret", TokenStream::new(), check)
+        }
+        Kind::Ensures => {
+            // The old values are only cloned when the postcondition is enabled on entry, and the
+            // postcondition is only checked if they were. Both use the same call site, so that
+            // switching it off also stops the cloning.
+            let level = args.level.assert_level();
+            let callsite = Ident::new(CALLSITE, Span::call_site());
+            let config = args.config.as_ref().map(|config| quote!(, #config));
+            let enabled = match &args.config {
+                Some(config) => quote! {
+                    #callsite.enabled(#level) && #level <= (#config).assertion_level()
+                },
+                None => quote!(#callsite.enabled(#level)),
+            };
+            let old = Ident::new(OLD, Span::call_site());
+            let names =
+                (0..olds.len()).map(|i| Ident::new(&format!("{}_{}", OLD, i), Span::call_site()));
+            let before = quote! {
+                ::invariants::__callsite_static!(
+                    #callsite = ::invariants::Callsite::__new(
+                        ::core::option::Option::Some(#level),
+                        ::core::option::Option::None,
+                        #text,
+                        ::invariants::__location!(),
+                    )
+                );
+                let #old = if #enabled {
+                    Some((#(::core::clone::Clone::clone(&(#olds)),)*))
+                } else {
+                    None
+                };
+            };
+            let after = quote! {
+                if let Some((#(#names,)*)) = #old {
+                    ::invariants::__assert_at_level!(
+                        @callsite #callsite, #level #config; #condition, "{}", #message
+                    );
+                }
+            };
+            wrap::wrap_body(&item.sig, &item.block, "ret", before, after)
+        }
+    });
+    Ok(item.into_token_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_is_replaced() {
+        let mut olds = Vec::new();
+        let tokens = take_old(quote!(self.len() == old(self.len()) + x.old(1)), &mut olds);
+        assert_eq!(
+            wrap::source_text(&tokens),
+            "self.len() == __invariants_old_0 + x.old(1)"
+        );
+        assert_eq!(olds.len(), 1);
+        assert_eq!(wrap::source_text(&olds[0]), "self.len()");
+    }
+
+    #[test]
+    fn implication_is_rewritten() {
+        let tokens = implications(quote!(ret.is_some() -> (a -> b)));
+        assert_eq!(
+            wrap::source_text(&tokens),
+            "!(ret.is_some()) || ((!(a) || (b)))"
+        );
+        let tokens = implications(quote!(a - b > c));
+        assert_eq!(tokens.to_string(), quote!(a - b > c).to_string());
+    }
+
+    #[test]
+    fn closure_return_type_is_not_an_implication() {
+        let tokens = implications(quote!(items.iter().all(|x| -> bool { *x > 0 }) -> ok));
+        assert_eq!(
+            wrap::source_text(&tokens),
+            "!(items.iter().all(|x| -> bool { *x > 0 })) || (ok)"
+        );
+        let tokens = implications(quote!(f(|| -> u32 { 1 })));
+        assert_eq!(wrap::source_text(&tokens), "f(|| -> u32 { 1 })");
+    }
+}
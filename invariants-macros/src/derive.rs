@@ -0,0 +1,136 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Data, DeriveInput, Expr, Fields, Member, Meta, Token};
+
+use crate::level::Level;
+use crate::wrap;
+
+/// A constraint on a field, from `#[inv(level, constraint)]`.
+enum Constraint {
+    Sorted,
+    Range(Expr),
+    NonEmpty,
+    Nested,
+}
+
+struct FieldConstraint {
+    level: Level,
+    constraint: Constraint,
+}
+
+fn parse_constraints(attr: &syn::Attribute) -> syn::Result<Vec<FieldConstraint>> {
+    let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+    let mut level = Level::Trace;
+    let mut constraints = Vec::new();
+    for meta in metas {
+        let name = meta.path().get_ident().map(|ident| ident.to_string());
+        let constraint = match (&meta, name.as_deref()) {
+            (Meta::Path(_), Some(name)) if Level::from_name(name).is_some() => {
+                level = Level::from_name(name).unwrap();
+                continue;
+            }
+            (Meta::Path(_), Some("sorted")) => Constraint::Sorted,
+            (Meta::Path(_), Some("non_empty")) => Constraint::NonEmpty,
+            (Meta::Path(_), Some("nested")) => Constraint::Nested,
+            (Meta::NameValue(meta), Some("range")) => Constraint::Range(meta.value.clone()),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected a level, `sorted`, `range = ..`, `non_empty` or `nested`",
+                ))
+            }
+        };
+        constraints.push(FieldConstraint { level, constraint });
+    }
+    Ok(constraints)
+}
+
+pub(crate) fn expand(input: TokenStream) -> syn::Result<TokenStream> {
+    let mut input: DeriveInput = syn::parse2(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => Vec::new(),
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Invariant` can only be derived for structs",
+            ))
+        }
+    };
+    let name = input.ident.to_string();
+    let mut checks = Vec::new();
+    let mut nested_types = Vec::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+        let field_name = wrap::source_text(&member);
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("inv"))
+        {
+            for FieldConstraint { level, constraint } in parse_constraints(attr)? {
+                let level = level.assert_level();
+                let (condition, description): (Expr, String) = match constraint {
+                    Constraint::Sorted => (
+                        parse_quote!(is_sorted(&self.#member)),
+                        "is not sorted".to_string(),
+                    ),
+                    Constraint::Range(range) => (
+                        parse_quote!((#range).contains(&self.#member)),
+                        format!("is not in range `{}`", wrap::source_text(&range)),
+                    ),
+                    Constraint::NonEmpty => (
+                        parse_quote!(!self.#member.is_empty()),
+                        "is empty".to_string(),
+                    ),
+                    Constraint::Nested => {
+                        nested_types.push(field.ty.clone());
+                        checks.push(quote! {
+                            if #level <= config.assertion_level() {
+                                ::invariants::Invariant::check(&self.#member, config)?;
+                            }
+                        });
+                        continue;
+                    }
+                };
+                let text = wrap::source_text(&condition);
+                let message = format!("field `{}` of `{}` {}", field_name, name, description);
+                // Gated like `tcheck!(config; ..)`, so the constraint is a call site of its own.
+                checks.push(quote! {
+                    ::invariants::__check_at_level!(
+                        @text #text, #level, config; #condition, "{}", #message
+                    )?;
+                });
+            }
+        }
+    }
+
+    let where_clause = input.generics.make_where_clause();
+    for ty in nested_types {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::invariants::Invariant));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::invariants::Invariant for #ident #ty_generics #where_clause {
+            fn check(
+                &self,
+                config: &::invariants::AssertConfig,
+            ) -> ::core::result::Result<(), ::invariants::InvariantViolation> {
+                #[allow(unused_imports)]
+                use ::invariants::__private::is_sorted;
+                #(#checks)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
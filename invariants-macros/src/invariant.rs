@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, ImplItem, ItemImpl, MetaNameValue, Token, Visibility};
+
+use crate::level::Level;
+use crate::wrap;
+
+/// The arguments of `#[invariant(level = "trace", check = Self::well_formed)]`.
+struct InvariantArgs {
+    level: Level,
+    check: Expr,
+}
+
+impl Parse for InvariantArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)?;
+        let mut level = None;
+        let mut check = None;
+        for arg in args {
+            if arg.path.is_ident("level") {
+                level = Some(Level::from_expr(&arg.value)?);
+            } else if arg.path.is_ident("check") {
+                check = Some(arg.value);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    arg.path,
+                    "expected `level` or `check`",
+                ));
+            }
+        }
+        let check = check.ok_or_else(|| input.error("missing `check = ...` argument"))?;
+        Ok(InvariantArgs {
+            level: level.unwrap_or(Level::Trace),
+            check,
+        })
+    }
+}
+
+pub(crate) fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args: InvariantArgs = syn::parse2(args)?;
+    let mut item: ItemImpl = syn::parse2(item)?;
+    let assert = args.level.macro_path("assert");
+    let check = &args.check;
+    let check_text = wrap::source_text(check);
+
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        if wrap::take_skip(&mut method.attrs, "invariant")?
+            || !matches!(method.vis, Visibility::Public(_))
+            || !wrap::has_mut_self(&method.sig)
+            || method.sig.asyncness.is_some()
+            || method.sig.constness.is_some()
+        {
+            continue;
+        }
+        let name = wrap::fn_name(&method.sig);
+        let before = quote! {
+            #assert!(
+                (#check)(self),
+                "invariant `{}` violated on entry to `{}`",
+                #check_text,
+                #name
+            );
+        };
+        // The result may borrow `self`, so the invariant can only be checked on entry.
+        let after = if wrap::returns_borrow(&method.sig) {
+            TokenStream::new()
+        } else {
+            let deny_hidden_lifetimes = wrap::deny_hidden_lifetimes(&method.sig);
+            quote! {
+                #deny_hidden_lifetimes
+                #assert!(
+                    (#check)(self),
+                    "invariant `{}` violated on exit from `{}`",
+                    #check_text,
+                    #name
+                );
+            }
+        };
+        method.block = wrap::wrap_body(&method.sig, &method.block, wrap::RET, before, after);
+    }
+    Ok(item.into_token_stream())
+}
@@ -0,0 +1,78 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{Expr, ExprLit, ExprPath, Lit};
+
+/// An assertion level, as written in a macro argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    /// Parses `trace` or `"trace"`.
+    pub(crate) fn from_expr(expr: &Expr) -> syn::Result<Self> {
+        let name = match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => lit.value(),
+            Expr::Path(ExprPath { path, .. }) if path.get_ident().is_some() => {
+                path.get_ident().unwrap().to_string()
+            }
+            _ => String::new(),
+        };
+        Self::from_name(&name).ok_or_else(|| {
+            syn::Error::new_spanned(
+                expr,
+                "expected one of `error`, `warn`, `info`, `debug` or `trace`",
+            )
+        })
+    }
+
+    /// Returns the name of the level, as accepted by [`Level::from_name`].
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Level::Error => "e",
+            Level::Warn => "w",
+            Level::Info => "i",
+            Level::Debug => "d",
+            Level::Trace => "t",
+        }
+    }
+
+    /// Returns the `invariants::AssertLevel` of this level.
+    pub(crate) fn assert_level(self) -> TokenStream {
+        let name = Ident::new(&format!("{:?}", self), Span::call_site());
+        quote!(::invariants::AssertLevel::#name)
+    }
+
+    /// Returns the path of the leveled macro with the given suffix, like `::invariants::tassert`.
+    pub(crate) fn macro_path(self, suffix: &str) -> TokenStream {
+        let name = Ident::new(&format!("{}{}", self.prefix(), suffix), Span::call_site());
+        quote!(::invariants::#name)
+    }
+}
@@ -0,0 +1,264 @@
+/*!
+Procedural macros for the [`invariants`](https://docs.rs/invariants) crate.
+
+Use them through the re-exports in `invariants`, which are enabled by its `macros` feature.
+*/
+
+use proc_macro::TokenStream;
+
+mod contract;
+mod derive;
+mod invariant;
+mod level;
+mod loops;
+mod trait_invariant;
+mod wrap;
+
+/// Checks a struct invariant on entry to and exit from every public `&mut self` method of an
+/// `impl` block.
+///
+/// `check` is a function or closure taking `&Self` and returning whether the invariant holds.
+/// `level` is the assertion level of the checks, `trace` by default, and is gated like
+/// `tassert!`. Methods returning a borrow are only checked on entry, and a method marked with
+/// `#[invariant(skip)]` is not checked at all. `'static` borrows don't count, and a lifetime elided
+/// in a path of the return type is rejected, so `Ref<T>` must be written `Ref<'_, T>`.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::invariant;
+///
+/// struct UniqueVec(Vec<u32>);
+///
+/// #[invariant(level = "trace", check = Self::well_formed)]
+/// impl UniqueVec {
+///     fn well_formed(&self) -> bool {
+///         self.0.iter().enumerate().all(|(i, x)| !self.0[..i].contains(x))
+///     }
+///
+///     pub fn insert(&mut self, x: u32) {
+///         if !self.0.contains(&x) {
+///             self.0.push(x);
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut set = UniqueVec(Vec::new());
+/// set.insert(1);
+/// set.insert(1);
+/// assert_eq!(set.0, [1]);
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn invariant(args: TokenStream, item: TokenStream) -> TokenStream {
+    invariant::expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Checks a precondition on entry to a function.
+///
+/// The condition is checked at the given `level`, `debug` by default, and is gated like
+/// `dassert!`. `config = CONFIG` passes an `AssertConfig` to the check. `a -> b` can be used for
+/// implications and means `!(a) || (b)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::requires;
+///
+/// #[requires(level = debug, idx < items.len())]
+/// fn get(items: &[u32], idx: usize) -> u32 {
+///     items[idx]
+/// }
+///
+/// # fn main() {
+/// assert_eq!(get(&[1, 2, 3], 1), 2);
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn requires(args: TokenStream, item: TokenStream) -> TokenStream {
+    contract::expand(contract::Kind::Requires, args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Checks a postcondition when a function returns.
+///
+/// Takes the same arguments as [`macro@requires`]. The condition can refer to the return value as
+/// `ret`, and to a clone of the value of `expr` on entry as `old(expr)`. The old values are only
+/// cloned when the postcondition is enabled. Early returns through `?` or `return` are handled like
+/// normal returns, but functions returning a borrow can not use `self` in their postconditions.
+/// The postcondition is not checked when the body panics.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::ensures;
+///
+/// #[ensures(level = trace, ret.is_some() -> items.contains(&ret.unwrap()))]
+/// fn max(items: &[u32]) -> Option<u32> {
+///     items.iter().copied().max()
+/// }
+///
+/// #[ensures(items.len() == old(items.len()) + 1)]
+/// fn push(items: &mut Vec<u32>, x: u32) {
+///     items.push(x);
+/// }
+///
+/// # fn main() {
+/// let mut items = vec![3, 1];
+/// push(&mut items, 2);
+/// assert_eq!(max(&items), Some(3));
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn ensures(args: TokenStream, item: TokenStream) -> TokenStream {
+    contract::expand(contract::Kind::Ensures, args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `invariants::Invariant` from constraints on the fields of a struct.
+///
+/// Each `#[inv(level, constraints...)]` attribute on a field adds constraints checked at `level`,
+/// `trace` by default. The constraints are:
+///
+/// - `sorted`: iterating over a reference to the field yields sorted items.
+/// - `range = a..b`: the field is in the range, which can be any range expression.
+/// - `non_empty`: the field's `is_empty()` returns false.
+/// - `nested`: the field's own `Invariant` holds, checked with the same `AssertConfig`.
+///
+/// Each constraint is checked like `tcheck!(config; ..)` at its level, so it is skipped unless both
+/// the `AssertConfig` passed to `check` and the assertion levels enable it, and it is listed by
+/// `invariants::sites()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{tassert_invariant, Invariant};
+///
+/// #[derive(Invariant)]
+/// struct Histogram {
+///     #[inv(error, non_empty)]
+///     #[inv(trace, sorted)]
+///     bounds: Vec<u32>,
+///     #[inv(debug, range = 0..100)]
+///     percentile: u8,
+/// }
+///
+/// # fn main() {
+/// let histogram = Histogram { bounds: vec![1, 10, 100], percentile: 50 };
+/// tassert_invariant!(histogram);
+/// # }
+/// ```
+#[proc_macro_derive(Invariant, attributes(inv))]
+pub fn derive_invariant(input: TokenStream) -> TokenStream {
+    derive::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Checks the loop invariants of the loops in a function.
+///
+/// A loop invariant is declared with a `#[loop_invariant(level, cond)]` attribute on a `for`,
+/// `while` or `loop` expression, or with `loop_invariant!(level; cond)` statements at the start of
+/// its body. The level is `trace` by default. Each invariant is checked on entry to the loop,
+/// after every iteration, including ones ended by `continue`, and when the loop exits normally or
+/// through `break`. Failure messages include the number of the iteration.
+///
+/// Since they are also checked outside the loop, the conditions can only use variables declared
+/// before the loop, and not the loop's pattern.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::loop_invariants;
+///
+/// #[loop_invariants]
+/// fn binary_search(items: &[u32], x: u32) -> Option<usize> {
+///     let (mut lo, mut hi) = (0, items.len());
+///     #[loop_invariant(trace, lo <= hi && hi <= items.len())]
+///     while lo < hi {
+///         let mid = lo + (hi - lo) / 2;
+///         match items[mid].cmp(&x) {
+///             std::cmp::Ordering::Less => lo = mid + 1,
+///             std::cmp::Ordering::Greater => hi = mid,
+///             std::cmp::Ordering::Equal => return Some(mid),
+///         }
+///     }
+///     None
+/// }
+///
+/// # fn main() {
+/// assert_eq!(binary_search(&[1, 3, 5], 3), Some(1));
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn loop_invariants(args: TokenStream, item: TokenStream) -> TokenStream {
+    loops::expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Declares an invariant of a trait, checked in the methods of its implementors.
+///
+/// On a trait, `#[trait_invariant(level, cond)]` declares an invariant every implementor must
+/// keep. `cond` can use `self` and the trait's methods. The level is `trace` by default, and
+/// `#[trait_invariant(level, config = CONFIG, cond)]` also passes an `AssertConfig` to the check.
+///
+/// The trait itself is left as written. Next to it, an extension trait named after it, such as
+/// `CollectionChecked` for `Collection`, is implemented for every implementor. For each `&mut self`
+/// method `m` of the trait, it has a `checked_m` method calling `m` between checks of all the
+/// invariants, on entry and on exit, like [`macro@invariant`]. As there, methods returning a borrow
+/// are only checked on entry, and methods marked with `#[trait_invariant(skip)]` get no `checked_`
+/// method. Implementors can not override the checks.
+///
+/// An `impl` of the trait can also be marked with `#[trait_invariant]`, so that its own `&mut self`
+/// methods check the invariants, and plain calls are checked too. There, `#[trait_invariant(skip)]`
+/// leaves a method unchecked. Unmarked impls, including the ones in other crates, compile as
+/// before.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::trait_invariant;
+///
+/// #[trait_invariant(debug, self.len() == self.iter().count())]
+/// trait Collection {
+///     fn len(&self) -> usize;
+///     fn iter(&self) -> std::slice::Iter<'_, u32>;
+///     fn push(&mut self, x: u32);
+/// }
+///
+/// struct Stack(Vec<u32>);
+///
+/// #[trait_invariant]
+/// impl Collection for Stack {
+///     fn len(&self) -> usize {
+///         self.0.len()
+///     }
+///
+///     fn iter(&self) -> std::slice::Iter<'_, u32> {
+///         self.0.iter()
+///     }
+///
+///     fn push(&mut self, x: u32) {
+///         self.0.push(x);
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut stack = Stack(Vec::new());
+/// stack.push(1);
+/// stack.checked_push(2);
+/// assert_eq!(stack.len(), 2);
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn trait_invariant(args: TokenStream, item: TokenStream) -> TokenStream {
+    trait_invariant::expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,260 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+use syn::{
+    parse_quote, parse_quote_spanned, Attribute, Block, Expr, ExprBreak, Ident, ItemFn, Lifetime,
+    Path, Stmt, Token,
+};
+
+use crate::level::Level;
+use crate::wrap;
+
+/// The name of the binding counting the iterations of a loop with invariants.
+const ITERATION: &str = "__invariants_iteration";
+
+/// A loop invariant, from `#[loop_invariant(level, cond)]` or `loop_invariant!(level; cond)`.
+struct LoopInvariant {
+    /// The path of the `loop_invariant!` macro used to check it.
+    path: Path,
+    level: Level,
+    condition: Expr,
+    message: Option<TokenStream>,
+}
+
+impl LoopInvariant {
+    /// Parses `[level <sep>] cond`.
+    fn parse_with<P: Parse>(path: Path, input: ParseStream) -> syn::Result<Self> {
+        let mut level = Level::Trace;
+        let fork = input.fork();
+        if let Ok(ident) = fork.parse::<Ident>() {
+            if let Some(parsed) = Level::from_name(&ident.to_string()) {
+                if fork.parse::<P>().is_ok() {
+                    level = parsed;
+                    input.parse::<Ident>()?;
+                    input.parse::<P>()?;
+                }
+            }
+        }
+        let condition = input.parse()?;
+        let mut message = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            message = Some(input.parse()?);
+        }
+        Ok(LoopInvariant {
+            path,
+            level,
+            condition,
+            message,
+        })
+    }
+
+    /// Returns the check of the invariant, with `when` describing when it is checked.
+    fn check(&self, when: TokenStream) -> TokenStream {
+        let LoopInvariant {
+            path,
+            level,
+            condition,
+            message,
+        } = self;
+        let level = Ident::new(level.name(), Span::call_site());
+        let text = wrap::source_text(condition);
+        let message = message.as_ref().map(|message| quote!(, #message));
+        // The location of the check is that of the invariant, not that of the function.
+        quote_spanned!(condition.span()=> #path!(@at [#when] #text #level; #condition #message);)
+    }
+}
+
+/// Removes the `#[loop_invariant(...)]` attributes and the leading `loop_invariant!(...)`
+/// statements of a loop, and returns the invariants they declare.
+fn take_invariants(
+    attrs: &mut Vec<Attribute>,
+    body: &mut Block,
+) -> syn::Result<Vec<LoopInvariant>> {
+    let mut invariants = Vec::new();
+    let mut error = None;
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("loop_invariant") {
+            return true;
+        }
+        let parser = |input: ParseStream| {
+            LoopInvariant::parse_with::<Token![,]>(
+                parse_quote_spanned!(attr.span()=> ::invariants::loop_invariant),
+                input,
+            )
+        };
+        match attr.parse_args_with(parser) {
+            Ok(invariant) => invariants.push(invariant),
+            Err(err) => error = Some(err),
+        }
+        false
+    });
+    if let Some(err) = error {
+        return Err(err);
+    }
+    let leading = body
+        .stmts
+        .iter()
+        .take_while(|stmt| match stmt {
+            Stmt::Macro(stmt) => stmt
+                .mac
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "loop_invariant"),
+            _ => false,
+        })
+        .count();
+    for stmt in body.stmts.drain(..leading) {
+        if let Stmt::Macro(stmt) = stmt {
+            let path = stmt.mac.path.clone();
+            let parser = |input: ParseStream| LoopInvariant::parse_with::<Token![;]>(path, input);
+            invariants.push(stmt.mac.parse_body_with(parser)?);
+        }
+    }
+    Ok(invariants)
+}
+
+/// Returns whether `body` contains a `break` leaving the loop it belongs to, which has the given
+/// label. A `break` inside a macro invocation is not seen.
+fn breaks_out(body: &Block, label: Option<&Lifetime>) -> bool {
+    struct Finder<'a> {
+        label: Option<&'a Lifetime>,
+        /// How many loops inside `body` the visitor is in.
+        depth: usize,
+        found: bool,
+    }
+    impl Visit<'_> for Finder<'_> {
+        fn visit_expr_break(&mut self, expr: &ExprBreak) {
+            self.found |= match &expr.label {
+                Some(label) => self.label == Some(label),
+                None => self.depth == 0,
+            };
+            syn::visit::visit_expr_break(self, expr);
+        }
+        fn visit_expr_for_loop(&mut self, expr: &syn::ExprForLoop) {
+            self.depth += 1;
+            syn::visit::visit_expr_for_loop(self, expr);
+            self.depth -= 1;
+        }
+        fn visit_expr_while(&mut self, expr: &syn::ExprWhile) {
+            self.depth += 1;
+            syn::visit::visit_expr_while(self, expr);
+            self.depth -= 1;
+        }
+        fn visit_expr_loop(&mut self, expr: &syn::ExprLoop) {
+            self.depth += 1;
+            syn::visit::visit_expr_loop(self, expr);
+            self.depth -= 1;
+        }
+        // A `break` can not leave closures, async blocks and items.
+        fn visit_expr_closure(&mut self, _: &syn::ExprClosure) {}
+        fn visit_expr_async(&mut self, _: &syn::ExprAsync) {}
+        fn visit_item(&mut self, _: &syn::Item) {}
+    }
+    let mut finder = Finder {
+        label,
+        depth: 0,
+        found: false,
+    };
+    finder.visit_block(body);
+    finder.found
+}
+
+struct Rewriter {
+    error: Option<syn::Error>,
+}
+
+impl VisitMut for Rewriter {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        syn::visit_mut::visit_expr_mut(self, expr);
+        let taken = match expr {
+            Expr::ForLoop(expr) => take_invariants(&mut expr.attrs, &mut expr.body),
+            Expr::While(expr) => take_invariants(&mut expr.attrs, &mut expr.body),
+            Expr::Loop(expr) => take_invariants(&mut expr.attrs, &mut expr.body),
+            _ => return,
+        };
+        let invariants = match taken {
+            Ok(invariants) if invariants.is_empty() => return,
+            Ok(invariants) => invariants,
+            Err(err) => {
+                self.error.get_or_insert(err);
+                return;
+            }
+        };
+        let iteration = Ident::new(ITERATION, Span::call_site());
+        let on_entry: TokenStream = invariants
+            .iter()
+            .map(|inv| inv.check(quote!("on entry")))
+            .collect();
+        let after_iteration: TokenStream = invariants
+            .iter()
+            .map(|inv| inv.check(quote!("after iteration {}", #iteration)))
+            .collect();
+        let on_exit: TokenStream = invariants
+            .iter()
+            .map(|inv| inv.check(quote!("on exit after {} iterations", #iteration)))
+            .collect();
+        // The checks after each iteration run at the start of the next one, so that they also
+        // run after a `continue`.
+        let returns_value = matches!(expr, Expr::Loop(_));
+        // A `loop` without a `break` never exits, and checks after it would be unreachable code.
+        let exits = match expr {
+            Expr::Loop(expr) => {
+                breaks_out(&expr.body, expr.label.as_ref().map(|label| &label.name))
+            }
+            _ => true,
+        };
+        let body = match expr {
+            Expr::ForLoop(expr) => &mut expr.body,
+            Expr::While(expr) => &mut expr.body,
+            Expr::Loop(expr) => &mut expr.body,
+            _ => unreachable!(),
+        };
+        let stmts = &body.stmts;
+        *body = parse_quote!({
+            if #iteration > 0 {
+                #after_iteration
+            }
+            #iteration += 1;
+            #(#stmts)*
+        });
+        *expr = if !exits {
+            parse_quote!({
+                let mut #iteration: usize = 0;
+                #on_entry
+                #expr
+            })
+        } else if returns_value {
+            parse_quote!({
+                let mut #iteration: usize = 0;
+                #on_entry
+                let __invariants_value = #expr;
+                #on_exit
+                __invariants_value
+            })
+        } else {
+            parse_quote!({
+                let mut #iteration: usize = 0;
+                #on_entry
+                #expr
+                #on_exit
+            })
+        };
+    }
+}
+
+pub(crate) fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(args, "expected no arguments"));
+    }
+    let mut item: ItemFn = syn::parse2(item)?;
+    let mut rewriter = Rewriter { error: None };
+    rewriter.visit_block_mut(&mut item.block);
+    match rewriter.error {
+        Some(err) => Err(err),
+        None => Ok(item.into_token_stream()),
+    }
+}
@@ -0,0 +1,259 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    parse_quote, Block, Expr, FnArg, GenericParam, Ident, ImplItem, Item, ItemImpl, ItemTrait,
+    Signature, Token, TraitItem, TraitItemFn,
+};
+
+use crate::level::Level;
+use crate::wrap;
+
+/// A trait invariant, from `#[trait_invariant(level, config = CONFIG, cond)]`.
+struct TraitInvariant {
+    level: Level,
+    config: Option<Expr>,
+    condition: Expr,
+}
+
+impl Parse for TraitInvariant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut level = Level::Trace;
+        if input.peek(Ident) && input.peek2(Token![,]) {
+            let fork = input.fork();
+            if let Some(parsed) = Level::from_name(&fork.parse::<Ident>()?.to_string()) {
+                level = parsed;
+                input.parse::<Ident>()?;
+                input.parse::<Token![,]>()?;
+            }
+        }
+        let mut config = None;
+        if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+            let name: Ident = input.parse()?;
+            if name != "config" {
+                return Err(syn::Error::new_spanned(name, "expected `config`"));
+            }
+            input.parse::<Token![=]>()?;
+            config = Some(input.parse()?);
+            input.parse::<Token![,]>()?;
+        }
+        let condition = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(TraitInvariant {
+            level,
+            config,
+            condition,
+        })
+    }
+}
+
+/// Returns the name of the extension trait with the checks of the invariants of `trait_name`.
+fn checked_trait(trait_name: &Ident) -> Ident {
+    format_ident!("{}Checked", trait_name)
+}
+
+/// Returns whether the invariants are checked around a method.
+fn is_checked(sig: &Signature) -> bool {
+    wrap::has_mut_self(sig) && sig.asyncness.is_none() && sig.constness.is_none()
+}
+
+/// Returns the method of the extension trait calling `method` of `trait_path` between `check`s.
+fn checked_method(
+    trait_path: &TokenStream,
+    method: &TraitItemFn,
+    check: &TokenStream,
+) -> TraitItemFn {
+    let mut sig = method.sig.clone();
+    let name = &method.sig.ident;
+    sig.ident = format_ident!("checked_{}", name);
+
+    // The arguments are passed on by name, so their patterns are replaced by plain bindings.
+    let mut args = Vec::new();
+    for (i, input) in sig.inputs.iter_mut().skip(1).enumerate() {
+        if let FnArg::Typed(input) = input {
+            let arg = format_ident!("__invariants_arg{}", i);
+            *input.pat = parse_quote!(#arg);
+            args.push(arg);
+        }
+    }
+    let generics: Vec<TokenStream> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.to_token_stream()),
+            GenericParam::Const(param) => Some(param.ident.to_token_stream()),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = (!generics.is_empty()).then(|| quote!(::<#(#generics),*>));
+    let mut call = quote!(<Self as #trait_path>::#name #turbofish(self, #(#args),*));
+    if sig.unsafety.is_some() {
+        call = quote!(unsafe { #call });
+    }
+
+    let fn_name = wrap::fn_name(&method.sig);
+    let on_entry = format!("on entry to `{}`", fn_name);
+    let doc = format!(
+        "Calls `{}`, checking the trait invariants on entry and, unless it returns a borrow, on \
+         exit.",
+        fn_name
+    );
+    // A result that may borrow `self` means the invariants can only be checked on entry.
+    let body: Block = if wrap::returns_borrow(&sig) {
+        parse_quote!({
+            #check(self, #on_entry);
+            #call
+        })
+    } else {
+        let on_exit = format!("on exit from `{}`", fn_name);
+        let ret = Ident::new(wrap::RET, Span::call_site());
+        let deny_hidden_lifetimes = wrap::deny_hidden_lifetimes(&sig);
+        parse_quote!({
+            #deny_hidden_lifetimes
+            #check(self, #on_entry);
+            let #ret = #call;
+            #check(self, #on_exit);
+            #ret
+        })
+    };
+    parse_quote! {
+        #[doc = #doc]
+        #sig #body
+    }
+}
+
+/// Adds an extension trait with the checks of the invariants, implemented for all implementors of
+/// the trait. Further `#[trait_invariant]` attributes on the trait are handled here too, so there
+/// is only one check.
+fn expand_trait(args: TokenStream, mut item: ItemTrait) -> syn::Result<TokenStream> {
+    let mut invariants = vec![syn::parse2::<TraitInvariant>(args)?];
+    let mut error = None;
+    item.attrs.retain(|attr| {
+        if !attr.path().is_ident("trait_invariant") {
+            return true;
+        }
+        match attr.parse_args::<TraitInvariant>() {
+            Ok(invariant) => invariants.push(invariant),
+            Err(err) => error = Some(err),
+        }
+        false
+    });
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    let trait_ident = &item.ident;
+    let trait_name = trait_ident.to_string();
+    let checks = invariants.iter().map(|invariant| {
+        let assert = invariant.level.macro_path("assert");
+        let condition = &invariant.condition;
+        let text = wrap::source_text(condition);
+        let config = invariant.config.as_ref().map(|config| quote!(#config;));
+        quote! {
+            #assert!(
+                #config #condition,
+                "trait invariant `{}` of `{}` violated {}",
+                #text,
+                #trait_name,
+                when
+            );
+        }
+    });
+
+    let (_, ty_generics, where_clause) = item.generics.split_for_impl();
+    let trait_path = quote!(#trait_ident #ty_generics);
+    let checked = checked_trait(trait_ident);
+    let check = quote!(<Self as #checked #ty_generics>::__invariants_check);
+    let mut methods = Vec::new();
+    for trait_item in &mut item.items {
+        let TraitItem::Fn(method) = trait_item else {
+            continue;
+        };
+        if !wrap::take_skip(&mut method.attrs, "trait_invariant")? && is_checked(&method.sig) {
+            methods.push(checked_method(&trait_path, method, &check));
+        }
+    }
+
+    let vis = &item.vis;
+    let generics = &item.generics;
+    let doc = format!(
+        "Calls to the `&mut self` methods of [`{0}`] checking its trait invariants, for every \
+         implementor of `{0}`.",
+        trait_name
+    );
+    let mut impl_generics = item.generics.clone();
+    impl_generics
+        .params
+        .push(parse_quote!(__InvariantsSelf: ?::core::marker::Sized + #trait_path));
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    // The blanket impl is the only possible one, so implementors can not override the checks.
+    Ok(quote! {
+        #item
+
+        #[doc = #doc]
+        #vis trait #checked #generics: #trait_path #where_clause {
+            #[doc(hidden)]
+            fn __invariants_check(&self, when: &str) {
+                #(#checks)*
+            }
+
+            #(#methods)*
+        }
+
+        impl #impl_generics #checked #ty_generics for __InvariantsSelf #where_clause {}
+    })
+}
+
+/// Checks the trait's invariants on entry to and exit from every `&mut self` method of the impl.
+fn expand_impl(args: TokenStream, mut item: ItemImpl) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            args,
+            "the invariants are declared on the trait",
+        ));
+    }
+    let Some((_, trait_path, _)) = &item.trait_ else {
+        unreachable!("checked by `expand`");
+    };
+    let mut checked_path = trait_path.clone();
+    let last = checked_path.segments.last_mut().unwrap();
+    last.ident = checked_trait(&last.ident);
+    let check = quote!(<Self as #checked_path>::__invariants_check);
+
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(fn_item) = impl_item else {
+            continue;
+        };
+        if wrap::take_skip(&mut fn_item.attrs, "trait_invariant")? || !is_checked(&fn_item.sig) {
+            continue;
+        }
+        let name = wrap::fn_name(&fn_item.sig);
+        let on_entry = format!("on entry to `{}`", name);
+        let before = quote!(#check(self, #on_entry););
+        // The result may borrow `self`, so the invariants can only be checked on entry.
+        let after = if wrap::returns_borrow(&fn_item.sig) {
+            TokenStream::new()
+        } else {
+            let on_exit = format!("on exit from `{}`", name);
+            let deny_hidden_lifetimes = wrap::deny_hidden_lifetimes(&fn_item.sig);
+            quote! {
+                #deny_hidden_lifetimes
+                #check(self, #on_exit);
+            }
+        };
+        fn_item.block = wrap::wrap_body(&fn_item.sig, &fn_item.block, wrap::RET, before, after);
+    }
+    Ok(item.into_token_stream())
+}
+
+pub(crate) fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    match syn::parse2(item)? {
+        Item::Trait(item) => expand_trait(args, item),
+        Item::Impl(item) if item.trait_.is_some() => expand_impl(args, item),
+        item => Err(syn::Error::new_spanned(
+            item,
+            "`trait_invariant` can only be used on traits and trait impls",
+        )),
+    }
+}
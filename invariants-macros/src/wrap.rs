@@ -0,0 +1,367 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::visit::Visit;
+use syn::{
+    parse_quote, Attribute, Block, FnArg, Meta, ReturnType, Signature, Type, TypeImplTrait,
+    TypeReference,
+};
+
+/// The name of the binding holding the return value in checks run after the body.
+pub(crate) const RET: &str = "__invariants_ret";
+
+/// Wraps `body` so that `before` runs before it and `after` runs after it returns normally.
+///
+/// The body runs inside a closure, so `return` and `?` still leave only the body. `after` can
+/// refer to the return value as `ret`, usually [`RET`]. When `after` is empty, the statements of
+/// the body follow `before` directly, which also works for bodies returning a borrow of the
+/// arguments. `before` must then not declare any bindings.
+pub(crate) fn wrap_body(
+    sig: &Signature,
+    body: &Block,
+    ret: &str,
+    before: TokenStream,
+    after: TokenStream,
+) -> Block {
+    if after.is_empty() {
+        // Nesting the body in another block would make rustc warn about unused braces.
+        let stmts = &body.stmts;
+        return parse_quote!({
+            #before
+            #(#stmts)*
+        });
+    }
+    let ret = syn::Ident::new(ret, proc_macro2::Span::call_site());
+    let ret_ty = match &sig.output {
+        ReturnType::Default => quote!(-> ()),
+        ReturnType::Type(_, ty) if contains_impl_trait(ty) => TokenStream::new(),
+        ReturnType::Type(arrow, ty) => quote!(#arrow #ty),
+    };
+    parse_quote!({
+        #before
+        #[allow(clippy::redundant_closure_call)]
+        let #ret = (|| #ret_ty #body)();
+        #after
+        #ret
+    })
+}
+
+/// Returns whether the method is marked with `#[name(skip)]`, removing the marker.
+pub(crate) fn take_skip(attrs: &mut Vec<Attribute>, name: &str) -> syn::Result<bool> {
+    let mut skip = false;
+    let mut result = Ok(());
+    attrs.retain(|attr| match &attr.meta {
+        Meta::List(list) if list.path.is_ident(name) => {
+            match list.parse_args::<syn::Ident>() {
+                Ok(ident) if ident == "skip" => skip = true,
+                _ => {
+                    result = Err(syn::Error::new_spanned(
+                        attr,
+                        format!("expected `#[{}(skip)]`", name),
+                    ))
+                }
+            }
+            false
+        }
+        _ => true,
+    });
+    result.map(|()| skip)
+}
+
+/// Returns whether the method takes `&mut self`.
+pub(crate) fn has_mut_self(sig: &Signature) -> bool {
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) => {
+            receiver.reference.is_some() && receiver.mutability.is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether the return type may borrow from the arguments, in which case `self` can not
+/// be used again after the body ran. `'static` borrows don't count.
+///
+/// Lifetimes elided in a path, like the one of `Ref<T>`, can not be seen here, so checks after the
+/// body must come with [`deny_hidden_lifetimes`].
+pub(crate) fn returns_borrow(sig: &Signature) -> bool {
+    struct Finder(bool);
+    impl Visit<'_> for Finder {
+        fn visit_type_reference(&mut self, reference: &TypeReference) {
+            if reference.lifetime.is_none() {
+                self.0 = true;
+            }
+            syn::visit::visit_type_reference(self, reference);
+        }
+        fn visit_lifetime(&mut self, lifetime: &syn::Lifetime) {
+            if lifetime.ident != "static" {
+                self.0 = true;
+            }
+        }
+    }
+    match &sig.output {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ty) => {
+            let mut finder = Finder(false);
+            finder.visit_type(ty);
+            finder.0
+        }
+    }
+}
+
+/// Returns a statement rejecting lifetimes elided in the paths of the return type, like the one of
+/// `Ref<T>`. Such a result may borrow `self`, and the error asks for `Ref<'_, T>` instead, which
+/// [`returns_borrow`] sees, rather than failing to borrow `self` for the checks after the body.
+pub(crate) fn deny_hidden_lifetimes(sig: &Signature) -> TokenStream {
+    match &sig.output {
+        ReturnType::Type(_, ty) if !contains_impl_trait(ty) => quote! {
+            #[deny(elided_lifetimes_in_paths)]
+            let _: ::core::marker::PhantomData<#ty>;
+        },
+        _ => TokenStream::new(),
+    }
+}
+
+fn contains_impl_trait(ty: &Type) -> bool {
+    struct Finder(bool);
+    impl Visit<'_> for Finder {
+        fn visit_type_impl_trait(&mut self, _: &TypeImplTrait) {
+            self.0 = true;
+        }
+    }
+    let mut finder = Finder(false);
+    finder.visit_type(ty);
+    finder.0
+}
+
+/// Returns the name of a function, for failure messages.
+pub(crate) fn fn_name(sig: &Signature) -> String {
+    sig.ident.to_token_stream().to_string()
+}
+
+/// Formats tokens for failure messages, closer to how they were written than
+/// `TokenStream::to_string`, which puts spaces between all tokens. Literals are copied verbatim.
+pub(crate) fn source_text(tokens: &impl ToTokens) -> String {
+    let mut out = String::new();
+    write_stream(&mut out, tokens.to_token_stream());
+    out
+}
+
+/// The operators of more than one character, longest first.
+const OPERATORS: &[&str] = &[
+    "..=", "...", "<<=", ">>=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "..", "+=",
+    "-=", "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>",
+];
+
+/// Keywords after which an operator is a unary one, and a group is not called or indexed.
+const KEYWORDS: &[&str] = &[
+    "as", "break", "else", "for", "if", "in", "let", "match", "move", "mut", "return", "while",
+];
+
+/// A token of a token stream, with multi-character operators joined.
+enum Token {
+    Word(String),
+    Literal(String),
+    Op(String),
+    Group(Delimiter, TokenStream),
+}
+
+/// What came before a token in the same group, to decide whether it is preceded by a space.
+#[derive(Clone, Copy, PartialEq)]
+enum Prev {
+    Start,
+    /// An identifier, literal, group, `?` or closing `>` of generic arguments.
+    Operand,
+    Keyword,
+    /// A binary operator, or one that is always followed by a space, like `,`.
+    Spaced,
+    /// A prefix operator, `.`, `::`, `..`, the `|` opening closure parameters or the `'` of a
+    /// lifetime.
+    Glued,
+    /// The `!` of a macro call.
+    Bang,
+}
+
+fn tokens(stream: TokenStream) -> Vec<Token> {
+    let trees: Vec<TokenTree> = stream.into_iter().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < trees.len() {
+        match &trees[i] {
+            TokenTree::Ident(ident) => tokens.push(Token::Word(ident.to_string())),
+            TokenTree::Literal(literal) => tokens.push(Token::Literal(literal.to_string())),
+            TokenTree::Group(group) => {
+                tokens.push(Token::Group(group.delimiter(), group.stream()));
+            }
+            TokenTree::Punct(_) => {
+                // The longest operator made of this punct and the ones joined to it.
+                let mut joined = String::new();
+                for tree in &trees[i..] {
+                    let TokenTree::Punct(punct) = tree else {
+                        break;
+                    };
+                    joined.push(punct.as_char());
+                    if punct.spacing() == Spacing::Alone || joined.len() == 3 {
+                        break;
+                    }
+                }
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| joined.starts_with(*op))
+                    .map_or(&joined[..1], |op| op);
+                i += op.len() - 1;
+                tokens.push(Token::Op(op.to_string()));
+            }
+        }
+        i += 1;
+    }
+    tokens
+}
+
+fn write_stream(out: &mut String, stream: TokenStream) {
+    let mut prev = Prev::Start;
+    // Whether we are between the `|`s of closure parameters.
+    let mut in_params = false;
+    // How many `<` of generic arguments are open.
+    let mut generics = 0;
+    let mut after_path = false;
+    for token in tokens(stream) {
+        let (space, next) = match &token {
+            Token::Word(word) if KEYWORDS.contains(&word.as_str()) => (true, Prev::Keyword),
+            Token::Word(_) => (true, Prev::Operand),
+            Token::Literal(_) => (true, Prev::Operand),
+            Token::Group(delimiter, _) => {
+                let glued =
+                    *delimiter != Delimiter::Brace && matches!(prev, Prev::Operand | Prev::Bang);
+                (!glued, Prev::Operand)
+            }
+            Token::Op(op) => {
+                let unary = !matches!(prev, Prev::Operand);
+                match op.as_str() {
+                    "," | ";" | ":" => (false, Prev::Spaced),
+                    "." | "::" => (prev != Prev::Operand, Prev::Glued),
+                    "?" => (false, Prev::Operand),
+                    ".." | "..=" => (false, Prev::Glued),
+                    "'" => (true, Prev::Glued),
+                    "!" if prev == Prev::Operand => (false, Prev::Bang),
+                    "!" | "-" | "*" | "&" | "&&" if unary => (true, Prev::Glued),
+                    "<" if after_path || generics > 0 => {
+                        generics += 1;
+                        (false, Prev::Glued)
+                    }
+                    ">" | ">>" if generics > 0 => {
+                        generics -= op.len().min(generics);
+                        (false, Prev::Operand)
+                    }
+                    "|" if unary && !in_params => {
+                        in_params = true;
+                        (true, Prev::Glued)
+                    }
+                    "|" if in_params => {
+                        in_params = false;
+                        (false, Prev::Spaced)
+                    }
+                    _ => (true, Prev::Spaced),
+                }
+            }
+        };
+        if space && !matches!(prev, Prev::Start | Prev::Glued | Prev::Bang) {
+            out.push(' ');
+        }
+        after_path = matches!(&token, Token::Op(op) if op == "::");
+        match token {
+            Token::Word(text) | Token::Literal(text) | Token::Op(text) => out.push_str(&text),
+            Token::Group(delimiter, stream) => {
+                let (open, close) = match delimiter {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace if stream.is_empty() => ("{", "}"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                out.push_str(open);
+                write_stream(out, stream);
+                out.push_str(close);
+            }
+        }
+        prev = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_text_removes_token_spacing() {
+        let expr: syn::Expr = syn::parse_quote!(Self::well_formed(&self.items[..n], vec![1, 2]));
+        assert_eq!(
+            source_text(&expr),
+            "Self::well_formed(&self.items[..n], vec![1, 2])"
+        );
+        let expr: syn::Expr = syn::parse_quote!(idx < self.len() && !old(x).is_empty());
+        assert_eq!(source_text(&expr), "idx < self.len() && !old(x).is_empty()");
+        let expr: syn::Expr = syn::parse_quote!(items.iter().all(|&x| x < pivot || x == 0));
+        assert_eq!(
+            source_text(&expr),
+            "items.iter().all(|&x| x < pivot || x == 0)"
+        );
+        let expr: syn::Expr = syn::parse_quote!(*value != -1 && a * b == (*c - 2));
+        assert_eq!(source_text(&expr), "*value != -1 && a * b == (*c - 2)");
+    }
+
+    #[test]
+    fn static_borrows_are_not_returned_borrows() {
+        let sig: Signature = syn::parse_quote!(fn f(&mut self) -> &'static str);
+        assert!(!returns_borrow(&sig));
+        let sig: Signature = syn::parse_quote!(fn f(&mut self) -> Foo<'static, &'static u8>);
+        assert!(!returns_borrow(&sig));
+        let sig: Signature = syn::parse_quote!(fn f(&mut self) -> (&'static str, &u8));
+        assert!(returns_borrow(&sig));
+        let sig: Signature = syn::parse_quote!(fn f(&mut self) -> Ref<'_, u8>);
+        assert!(returns_borrow(&sig));
+    }
+
+    #[test]
+    fn hidden_lifetimes_are_denied() {
+        let sig: Signature = syn::parse_quote!(fn f(&mut self) -> Ref<u8>);
+        assert!(!returns_borrow(&sig));
+        assert_eq!(
+            deny_hidden_lifetimes(&sig).to_string(),
+            quote! {
+                #[deny(elided_lifetimes_in_paths)]
+                let _: ::core::marker::PhantomData<Ref<u8> >;
+            }
+            .to_string()
+        );
+        let sig: Signature = syn::parse_quote!(fn f(&mut self) -> impl Iterator<Item = u8>);
+        assert!(deny_hidden_lifetimes(&sig).is_empty());
+    }
+
+    #[test]
+    fn source_text_copies_literals() {
+        let expr: syn::Expr = syn::parse_quote!(name == "a (b) , c" && sep != ' ' && x > 1.5e3);
+        assert_eq!(
+            source_text(&expr),
+            r#"name == "a (b) , c" && sep != ' ' && x > 1.5e3"#
+        );
+    }
+
+    #[test]
+    fn source_text_spaces_closures_and_generics() {
+        let tokens: TokenStream = "items.iter().all(|x: &u32| -> bool { *x > 0 })"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            source_text(&tokens),
+            "items.iter().all(|x: &u32| -> bool { *x > 0 })"
+        );
+        let tokens: TokenStream = "v.iter().sum::<Vec<u32>>() >= 0..=n && f(&'a x)"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            source_text(&tokens),
+            "v.iter().sum::<Vec<u32>>() >= 0..=n && f(&'a x)"
+        );
+        let tokens: TokenStream = "if a { b } else { !c }".parse().unwrap();
+        assert_eq!(source_text(&tokens), "if a { b } else { !c }");
+    }
+}
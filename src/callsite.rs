@@ -0,0 +1,505 @@
+//! Per call site caching of the runtime assert level, and the registry of call sites.
+//!
+//! Every leveled macro expansion owns a static [`Callsite`]. The first time it is reached, it
+//! resolves its level from the per-module directives and [`max_level()`](crate::max_level), and
+//! caches it along with the current generation. Changing either of those bumps the generation,
+//! which invalidates the cache of every call site at once, so the common path is two atomic loads.
+//! A call site that is switched off caches [`AssertLevel::Off`], so it costs the same.
+//!
+//! With the `static_sites` feature, the call sites are collected into a distributed slice at link
+//! time, so every call site in the binary is known from the start. Without it, call sites register
+//! themselves the first time they are reached, and [`set_site_enabled`] keeps a list of rules that
+//! are applied to every call site when it registers.
+
+use std::fmt;
+#[cfg(not(feature = "static_sites"))]
+use std::ptr;
+#[cfg(not(feature = "static_sites"))]
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+use crate::{filter, AssertLevel, Location};
+
+const UNRESOLVED: usize = 0;
+/// The cached level of a call site is the level plus one in the low bits, and the generation it
+/// was resolved in above them.
+const LEVEL_BITS: u32 = 3;
+const LEVEL_MASK: usize = (1 << LEVEL_BITS) - 1;
+
+/// Every call site in the binary.
+#[cfg(feature = "static_sites")]
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static CALLSITES: [Callsite];
+
+/// The head of the list of registered call sites.
+#[cfg(not(feature = "static_sites"))]
+static CALLSITES: AtomicPtr<Callsite> = AtomicPtr::new(ptr::null_mut());
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+/// The rules of [`set_site_enabled`]. Call sites register while holding the read lock, so a rule
+/// is either seen by a registering site or applied to it after it registered.
+static RULES: RwLock<Vec<(Selector, bool)>> = RwLock::new(Vec::new());
+
+/// Declares the static [`Callsite`] of a macro expansion. With the `static_sites` feature, it is
+/// added to the call sites collected at link time.
+#[cfg(feature = "static_sites")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __callsite_static {
+    ($name:ident = $callsite:expr) => {
+        #[$crate::__private::linkme::distributed_slice($crate::__private::CALLSITES)]
+        #[linkme(crate = $crate::__private::linkme)]
+        static $name: $crate::Callsite = $callsite;
+    };
+}
+
+/// Declares the static [`Callsite`] of a macro expansion. With the `static_sites` feature, it is
+/// added to the call sites collected at link time.
+#[cfg(not(feature = "static_sites"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __callsite_static {
+    ($name:ident = $callsite:expr) => {
+        static $name: $crate::Callsite = $callsite;
+    };
+}
+
+/// A call site of an assertion macro.
+///
+/// With the default `static_sites` feature, [`sites`] lists every call site in the binary, whether
+/// it was reached or not. Without it, call sites register themselves the first time they are
+/// reached, and only those are listed.
+#[derive(Debug)]
+pub struct Callsite {
+    level: Option<AssertLevel>,
+    id: Option<&'static str>,
+    condition: &'static str,
+    location: Location,
+    disabled: AtomicBool,
+    /// The resolved max level plus one and its generation, or `UNRESOLVED`.
+    cached: AtomicUsize,
+    #[cfg(not(feature = "static_sites"))]
+    registered: AtomicBool,
+    #[cfg(not(feature = "static_sites"))]
+    next: AtomicPtr<Callsite>,
+    #[cfg(feature = "stats")]
+    pub(crate) counters: crate::stats::Counters,
+}
+
+impl Callsite {
+    #[doc(hidden)]
+    pub const fn __new(
+        level: Option<AssertLevel>,
+        id: Option<&'static str>,
+        condition: &'static str,
+        location: Location,
+    ) -> Self {
+        Self {
+            level,
+            id,
+            condition,
+            location,
+            disabled: AtomicBool::new(false),
+            cached: AtomicUsize::new(UNRESOLVED),
+            #[cfg(not(feature = "static_sites"))]
+            registered: AtomicBool::new(false),
+            #[cfg(not(feature = "static_sites"))]
+            next: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "stats")]
+            counters: crate::stats::Counters::new(),
+        }
+    }
+
+    /// Returns the level of the assertion, or `None` for an
+    /// [`assert_enabled!`](crate::assert_enabled) call site, whose level is only known at runtime.
+    pub fn level(&self) -> Option<AssertLevel> {
+        self.level
+    }
+
+    /// Returns the ID given to the call site with `id = "name";`, if any.
+    pub fn id(&self) -> Option<&'static str> {
+        self.id
+    }
+
+    /// Returns the text of the checked condition, or an empty string if there is none.
+    pub fn condition(&self) -> &'static str {
+        self.condition
+    }
+
+    /// Returns the location of the call site.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Returns whether the call site is switched on, which is the default.
+    pub fn is_enabled(&self) -> bool {
+        !self.disabled.load(Ordering::Acquire)
+    }
+
+    /// Switches the call site on or off. A call site that is off never checks its assertion,
+    /// whatever the levels are.
+    pub fn set_enabled(&'static self, enabled: bool) {
+        self.disabled.store(!enabled, Ordering::Release);
+        invalidate_all();
+    }
+
+    /// Returns whether assertions of `level` are enabled at this call site on the current thread.
+    #[doc(hidden)]
+    pub fn enabled(&'static self, level: AssertLevel) -> bool {
+        level <= crate::STATIC_MAX_LEVEL
+            && level <= self.max_level()
+            && crate::thread_level().map_or(true, |thread| level <= thread)
+    }
+
+    /// Returns the runtime max level at this call site, ignoring any thread override. It is
+    /// [`AssertLevel::Off`] if the call site is switched off.
+    pub fn max_level(&'static self) -> AssertLevel {
+        let cached = self.cached.load(Ordering::Acquire);
+        let generation = GENERATION.load(Ordering::Acquire);
+        if cached & LEVEL_MASK == UNRESOLVED || cached & !LEVEL_MASK != generation << LEVEL_BITS {
+            return self.resolve();
+        }
+        AssertLevel::from_usize((cached & LEVEL_MASK) - 1)
+    }
+
+    /// Counts the call site as reached. Without the `static_sites` feature, this also registers it,
+    /// even if its level is compiled out. Does nothing without the `stats` feature.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __reached(&'static self) {
+        #[cfg(feature = "stats")]
+        {
+            #[cfg(not(feature = "static_sites"))]
+            self.register();
+            crate::stats::Counters::add(&self.counters.reached);
+        }
+    }
+
+    /// Counts the assertion as evaluated. Does nothing without the `stats` feature.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __evaluated(&self) {
+        #[cfg(feature = "stats")]
+        crate::stats::Counters::add(&self.counters.evaluated);
+    }
+
+    /// Counts the assertion as passed. Does nothing without the `stats` feature.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __passed(&self) {
+        #[cfg(feature = "stats")]
+        crate::stats::Counters::add(&self.counters.passed);
+    }
+
+    /// Counts the assertion as failed. Does nothing without the `stats` feature.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __failed(&self) {
+        #[cfg(feature = "stats")]
+        crate::stats::Counters::add(&self.counters.failed);
+    }
+
+    #[cold]
+    fn resolve(&'static self) -> AssertLevel {
+        #[cfg(not(feature = "static_sites"))]
+        self.register();
+        let generation = GENERATION.load(Ordering::SeqCst);
+        let level = if self.is_enabled() {
+            filter::module_level(self.location.module_path()).unwrap_or_else(crate::max_level)
+        } else {
+            AssertLevel::Off
+        };
+        // If the levels changed while we were resolving, the generation moved on, so the next
+        // call resolves again.
+        self.cached.store(
+            generation << LEVEL_BITS | (level as usize + 1),
+            Ordering::Release,
+        );
+        level
+    }
+
+    #[cfg(not(feature = "static_sites"))]
+    fn register(&'static self) {
+        if self.registered.load(Ordering::Acquire) {
+            return;
+        }
+        let rules = RULES.read().unwrap_or_else(|e| e.into_inner());
+        if self.registered.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        if let Some((_, enabled)) = rules
+            .iter()
+            .rev()
+            .find(|(selector, _)| selector.matches(self))
+        {
+            self.disabled.store(!enabled, Ordering::Release);
+        }
+        let this = self as *const Callsite as *mut Callsite;
+        let mut head = CALLSITES.load(Ordering::Acquire);
+        loop {
+            self.next.store(head, Ordering::Release);
+            match CALLSITES.compare_exchange_weak(head, this, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+impl fmt::Display for Callsite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.location)?;
+        if let Some(level) = self.level {
+            write!(f, " [{}]", level)?;
+        }
+        if let Some(id) = self.id {
+            write!(f, " {}:", id)?;
+        }
+        if !self.condition.is_empty() {
+            write!(f, " {}", self.condition)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the assertion call sites, in no particular order.
+///
+/// With the default `static_sites` feature, these are all the call sites in the binary, including
+/// the ones in code that never ran, or whose level is compiled out. Without it, these are only the
+/// call sites reached so far.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{sites, tassert};
+///
+/// fn pop(items: &mut Vec<u32>) -> Option<u32> {
+///     tassert!(!items.is_empty());
+///     items.pop()
+/// }
+///
+/// # fn main() {
+/// pop(&mut vec![1]);
+/// let site = sites().find(|site| site.condition() == "!items.is_empty()").unwrap();
+/// println!("{}", site);
+///
+/// site.set_enabled(false);
+/// assert_eq!(pop(&mut vec![]), None);
+/// # }
+/// ```
+#[cfg(feature = "static_sites")]
+pub fn sites() -> impl Iterator<Item = &'static Callsite> {
+    CALLSITES.iter()
+}
+
+/// Returns the assertion call sites, in no particular order.
+///
+/// With the default `static_sites` feature, these are all the call sites in the binary, including
+/// the ones in code that never ran, or whose level is compiled out. Without it, these are only the
+/// call sites reached so far.
+#[cfg(not(feature = "static_sites"))]
+pub fn sites() -> impl Iterator<Item = &'static Callsite> {
+    let mut next = CALLSITES.load(Ordering::Acquire);
+    std::iter::from_fn(move || {
+        // SAFETY: only `&'static Callsite`s are ever linked into the list.
+        let callsite = unsafe { next.as_ref() }?;
+        next = callsite.next.load(Ordering::Acquire);
+        Some(callsite)
+    })
+}
+
+/// Which call sites a rule of [`set_site_enabled`] applies to.
+#[derive(Debug, PartialEq, Eq)]
+enum Selector {
+    Location { file: String, line: u32 },
+    Id(String),
+}
+
+impl Selector {
+    fn parse(selector: &str) -> Self {
+        if let Some((file, line)) = selector.rsplit_once(':') {
+            if let Ok(line) = line.parse() {
+                return Selector::Location {
+                    file: file.to_string(),
+                    line,
+                };
+            }
+        }
+        Selector::Id(selector.to_string())
+    }
+
+    fn matches(&self, callsite: &Callsite) -> bool {
+        match self {
+            Selector::Id(id) => callsite.id == Some(id.as_str()),
+            Selector::Location { file, line } => {
+                let location = callsite.location();
+                location.line() == *line
+                    && location
+                        .file()
+                        .strip_suffix(file.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.ends_with(['/', '\\']))
+            }
+        }
+    }
+}
+
+/// Switches the call sites matching `selector` on or off, and returns how many of the call sites
+/// listed by [`sites`] matched. Without the `static_sites` feature, this includes the ones
+/// registered later.
+///
+/// The selector is either a `file:line` location, where the file may be a suffix of the path
+/// like `lib.rs` or `src/lib.rs`, or the ID of the call site given with `id = "name";`. Later
+/// calls override earlier ones. A call site that is off never checks its assertion, whatever the
+/// levels are.
+///
+/// All the leveled assertion, check and marker macros accept the `id = "name";` prefix. The
+/// call sites of [`loop_invariant!`](crate::loop_invariant), of the attribute macros and of the
+/// derived constraints have no ID, and are selected by their location.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{set_site_enabled, tassert};
+///
+/// # fn main() {
+/// set_site_enabled("hashset.unique", false);
+/// tassert!(id = "hashset.unique"; 1 + 1 == 3);
+/// # }
+/// ```
+pub fn set_site_enabled(selector: &str, enabled: bool) -> usize {
+    let selector = Selector::parse(selector);
+    let mut rules = RULES.write().unwrap_or_else(|e| e.into_inner());
+    let mut matched = 0;
+    for callsite in sites().filter(|callsite| selector.matches(callsite)) {
+        callsite.set_enabled(enabled);
+        matched += 1;
+    }
+    rules.retain(|(rule, _)| *rule != selector);
+    rules.push((selector, enabled));
+    matched
+}
+
+/// Drops the cached level of every call site, by starting a new generation. Must be called after
+/// any change that affects [`Callsite::max_level`].
+pub(crate) fn invalidate_all() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dassert_eq, iassert_matches, tassert, tassert_invariant, tassert_ne, tcheck, tunreachable,
+        wassert, AssertConfig,
+    };
+
+    fn find(condition: &str) -> &'static Callsite {
+        sites()
+            .find(|site| {
+                site.condition() == condition && site.location().module_path() == module_path!()
+            })
+            .expect("call site not registered")
+    }
+
+    #[test]
+    fn sites_are_registered_when_reached() {
+        let _lock = crate::tests::level_lock();
+        let value = Some(3);
+        dassert_eq!(value.map(|x| x + 1), Some(4));
+        iassert_matches!(value, Some(x) if x > 2);
+        let eq = find("value.map(|x| x + 1) == Some(4)");
+        assert_eq!(eq.level(), Some(AssertLevel::Debug));
+        assert_eq!(eq.location().module_path(), module_path!());
+        let matches = find("value matches Some(x) if x > 2");
+        assert_eq!(matches.level(), Some(AssertLevel::Info));
+        assert!(matches
+            .to_string()
+            .ends_with(" [info] value matches Some(x) if x > 2"));
+    }
+
+    #[test]
+    #[cfg(feature = "static_sites")]
+    fn sites_are_listed_before_they_are_reached() {
+        #[allow(dead_code)]
+        fn never_called() {
+            tassert!(id = "callsite.never"; false);
+        }
+        let site = sites()
+            .find(|site| site.id() == Some("callsite.never"))
+            .unwrap();
+        assert_eq!(site.level(), Some(AssertLevel::Trace));
+    }
+
+    #[test]
+    fn disabled_sites_are_skipped() {
+        let _lock = crate::tests::level_lock();
+        fn check(value: u32) {
+            tassert!(value < 8);
+        }
+        check(1);
+        let site = find("value < 8");
+        site.set_enabled(false);
+        assert!(!site.is_enabled());
+        assert_eq!(site.max_level(), AssertLevel::Off);
+        check(10);
+        site.set_enabled(true);
+        assert!(std::panic::catch_unwind(|| check(10)).is_err());
+    }
+
+    #[test]
+    fn sites_are_switched_off_by_id_and_location() {
+        let _lock = crate::tests::level_lock();
+        fn check(value: u32) {
+            wassert!(id = "callsite.test"; value > 0, "value is {}", value);
+            tassert!(value < 10);
+        }
+        let line = line!() - 2;
+        // Without `static_sites`, the call sites are not registered before they are reached.
+        let known = usize::from(cfg!(feature = "static_sites"));
+        assert_eq!(set_site_enabled("callsite.test", false), known);
+        assert_eq!(
+            set_site_enabled(&format!("callsite.rs:{}", line), false),
+            known
+        );
+        check(0);
+        check(200);
+        assert_eq!(find("value > 0").id(), Some("callsite.test"));
+        assert!(!find("value > 0").is_enabled());
+        assert!(!find("value < 10").is_enabled());
+
+        assert_eq!(set_site_enabled(&format!("ite.rs:{}", line), false), 0);
+        assert_eq!(
+            set_site_enabled(&format!("src/callsite.rs:{}", line), true),
+            1
+        );
+        assert!(std::panic::catch_unwind(|| check(200)).is_err());
+        assert_eq!(set_site_enabled("callsite.test", true), 1);
+        assert!(std::panic::catch_unwind(|| check(0)).is_err());
+    }
+
+    #[test]
+    fn every_leveled_macro_accepts_an_id() {
+        let _lock = crate::tests::level_lock();
+        let config = AssertConfig::new(AssertLevel::Trace);
+        let known = 7 * usize::from(cfg!(feature = "static_sites"));
+        assert_eq!(set_site_enabled("callsite.every", false), known);
+        let value = Some(1);
+        tassert_ne!(id = "callsite.every"; 1, 1);
+        tassert_ne!(id = "callsite.every"; config; 1, 1, "{} is 1", 1);
+        iassert_matches!(id = "callsite.every"; value, None);
+        tassert_invariant!(id = "callsite.every"; vec![value]);
+        assert_eq!(
+            tcheck!(id = "callsite.every"; config; value.is_none()),
+            Ok(())
+        );
+        tunreachable!(id = "callsite.every");
+        tunreachable!(id = "callsite.every"; "never reached");
+        let ids = sites()
+            .filter(|site| site.id() == Some("callsite.every"))
+            .inspect(|site| assert!(!site.is_enabled()))
+            .count();
+        assert_eq!(ids, 7);
+        assert_eq!(set_site_enabled("callsite.every", true), 7);
+    }
+}
@@ -0,0 +1,135 @@
+//! User-defined assertion categories.
+//!
+//! A category is a named switch that is enabled independently of the assert levels. Declare one
+//! with [`invariant_category!`](crate::invariant_category) and check it with
+//! [`cassert!`](crate::cassert).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A named group of assertions that can be enabled or disabled at runtime.
+///
+/// Categories are usually declared with [`invariant_category!`](crate::invariant_category).
+#[derive(Debug)]
+pub struct Category {
+    name: &'static str,
+    enabled: AtomicBool,
+}
+
+impl Category {
+    /// Creates a new category, disabled by default.
+    pub const fn new(name: &'static str) -> Self {
+        Self::with_enabled(name, false)
+    }
+
+    /// Creates a new category that starts out enabled or disabled.
+    pub const fn with_enabled(name: &'static str, enabled: bool) -> Self {
+        Self {
+            name,
+            enabled: AtomicBool::new(enabled),
+        }
+    }
+
+    /// Returns the name of the category.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns whether assertions of this category are checked.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables this category, returning whether it was enabled before.
+    pub fn set_enabled(&self, enabled: bool) -> bool {
+        self.enabled.swap(enabled, Ordering::Relaxed)
+    }
+
+    /// Enables this category.
+    pub fn enable(&self) {
+        self.set_enabled(true);
+    }
+
+    /// Disables this category.
+    pub fn disable(&self) {
+        self.set_enabled(false);
+    }
+}
+
+/// Declares a static [`Category`] named after the given identifier.
+///
+/// Categories are disabled unless declared with `= enabled`.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::invariant_category;
+///
+/// invariant_category!(pub EXPENSIVE_GRAPH_CHECKS);
+/// invariant_category!(
+///     /// Checks that are cheap enough to always run.
+///     CHEAP_CHECKS = enabled
+/// );
+///
+/// fn main() {
+///     assert!(!EXPENSIVE_GRAPH_CHECKS.is_enabled());
+///     assert!(CHEAP_CHECKS.is_enabled());
+///     assert_eq!(EXPENSIVE_GRAPH_CHECKS.name(), "EXPENSIVE_GRAPH_CHECKS");
+/// }
+/// ```
+#[macro_export]
+macro_rules! invariant_category {
+    ($(#[$attr:meta])* $vis:vis $name:ident = enabled) => {
+        $(#[$attr])*
+        $vis static $name: $crate::Category =
+            $crate::Category::with_enabled(stringify!($name), true);
+    };
+    ($(#[$attr:meta])* $vis:vis $name:ident) => {
+        $(#[$attr])*
+        $vis static $name: $crate::Category = $crate::Category::new(stringify!($name));
+    };
+}
+
+/// Asserts that the given expression is true when the given category is enabled.
+///
+/// Categories are independent of the assert levels, except that all category assertions are
+/// compiled out when [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL) is `Off`.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::{cassert, invariant_category};
+///
+/// invariant_category!(EXPENSIVE_GRAPH_CHECKS);
+///
+/// # fn main() {
+///     cassert!(EXPENSIVE_GRAPH_CHECKS; false, "Disabled categories are not checked");
+///     EXPENSIVE_GRAPH_CHECKS.enable();
+///     cassert!(EXPENSIVE_GRAPH_CHECKS; false, "This will fail once the category is enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! cassert {
+    ($category:expr; $($arg:tt)*) => (
+        if $crate::STATIC_MAX_LEVEL > $crate::AssertLevel::Off && $category.is_enabled() {
+            $crate::__check!(None, Some($category.name()); $($arg)*);
+        }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    invariant_category!(TEST_CATEGORY);
+    invariant_category!(OTHER_CATEGORY = enabled);
+
+    #[test]
+    fn categories_are_independent() {
+        let _lock = crate::tests::level_lock();
+        cassert!(TEST_CATEGORY; 2 + 2 == 5);
+        assert!(OTHER_CATEGORY.is_enabled());
+        assert!(!TEST_CATEGORY.set_enabled(true));
+        let result = std::panic::catch_unwind(|| cassert!(TEST_CATEGORY; 2 + 2 == 5, "{}", 5));
+        assert!(result.is_err());
+        TEST_CATEGORY.disable();
+        cassert!(TEST_CATEGORY; 2 + 2 == 5);
+    }
+}
@@ -0,0 +1,862 @@
+//! Leveled comparison assertions.
+//!
+//! Every level has `eq`, `ne`, `lt`, `le`, `gt` and `ge` variants, like `tassert_eq!`. They accept
+//! the same optional `id = "name";` and `config;` prefixes as [`tassert!`](crate::tassert), and
+//! report both operands on failure, like `assert_eq!` does.
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __message {
+    () => {
+        None
+    };
+    ($($arg:tt)+) => {
+        Some(format_args!($($arg)+))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cmp {
+    ($level:expr, $op:tt; $left:expr, $right:expr $(, $($arg:tt)*)?) => (
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left $op *right) {
+                    $crate::__private::fail(
+                        &$crate::Failure::__new(
+                            Some($level),
+                            None,
+                            $crate::__cmp_condition!($op; $left, $right),
+                            $crate::__location!(),
+                            $crate::__message!($($($arg)*)?),
+                        )
+                        .__with_comparison(stringify!($op), left, right),
+                    );
+                }
+            }
+        }
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cmp_condition {
+    ($op:tt; $left:expr, $right:expr $(, $($arg:tt)*)?) => {
+        concat!(
+            stringify!($left),
+            " ",
+            stringify!($op),
+            " ",
+            stringify!($right)
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_cmp_at_level {
+    (@site $id:expr, $level:expr, $op:tt $(, $config:expr)?; $($arg:tt)*) => (
+        if $crate::__site_enabled!($level, $crate::__cmp_condition!($op; $($arg)*), $id)
+            $(&& $level <= $config.assertion_level())?
+        {
+            $crate::__cmp!($level, $op; $($arg)*);
+        }
+    );
+    ($level:expr, $op:tt; id = $id:literal; $config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!(@site Some($id), $level, $op, $config; $($arg)*)
+    );
+    ($level:expr, $op:tt; id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!(@site Some($id), $level, $op; $($arg)*)
+    );
+    ($level:expr, $op:tt, $config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!(@site None, $level, $op, $config; $($arg)*)
+    );
+    ($level:expr, $op:tt; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!(@site None, $level, $op; $($arg)*)
+    );
+}
+
+/// Asserts that the left expression is equal to the right one when Error level assertions are
+/// enabled.
+///
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_eq;
+/// # fn main() {
+///     eassert_eq!(1 + 1, 3, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_eq {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, ==; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, ==, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Error, ==; $($arg)*));
+}
+
+/// Asserts that the left expression is not equal to the right one when Error level assertions are
+/// enabled.
+///
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_ne;
+/// # fn main() {
+///     eassert_ne!(1 + 1, 2, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_ne {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, !=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, !=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Error, !=; $($arg)*));
+}
+
+/// Asserts that the left expression is less than the right one when Error level assertions are
+/// enabled.
+///
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_lt;
+/// # fn main() {
+///     eassert_lt!(3, 2, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_lt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, <; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, <, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Error, <; $($arg)*));
+}
+
+/// Asserts that the left expression is less than or equal to the right one when Error level
+/// assertions are enabled.
+///
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_le;
+/// # fn main() {
+///     eassert_le!(3, 2, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_le {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, <=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, <=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Error, <=; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than the right one when Error level assertions are
+/// enabled.
+///
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_gt;
+/// # fn main() {
+///     eassert_gt!(2, 3, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_gt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, >; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, >, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Error, >; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than or equal to the right one when Error level
+/// assertions are enabled.
+///
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_ge;
+/// # fn main() {
+///     eassert_ge!(2, 3, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_ge {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, >=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Error, >=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Error, >=; $($arg)*));
+}
+
+/// Asserts that the left expression is equal to the right one when Warn level assertions are
+/// enabled.
+///
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_eq;
+/// # fn main() {
+///     wassert_eq!(1 + 1, 3, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_eq {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, ==; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, ==, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, ==; $($arg)*));
+}
+
+/// Asserts that the left expression is not equal to the right one when Warn level assertions are
+/// enabled.
+///
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_ne;
+/// # fn main() {
+///     wassert_ne!(1 + 1, 2, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_ne {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, !=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, !=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, !=; $($arg)*));
+}
+
+/// Asserts that the left expression is less than the right one when Warn level assertions are
+/// enabled.
+///
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_lt;
+/// # fn main() {
+///     wassert_lt!(3, 2, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_lt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, <; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, <, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, <; $($arg)*));
+}
+
+/// Asserts that the left expression is less than or equal to the right one when Warn level
+/// assertions are enabled.
+///
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_le;
+/// # fn main() {
+///     wassert_le!(3, 2, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_le {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, <=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, <=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, <=; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than the right one when Warn level assertions are
+/// enabled.
+///
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_gt;
+/// # fn main() {
+///     wassert_gt!(2, 3, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_gt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, >; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, >, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, >; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than or equal to the right one when Warn level
+/// assertions are enabled.
+///
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_ge;
+/// # fn main() {
+///     wassert_ge!(2, 3, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_ge {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, >=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, >=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Warn, >=; $($arg)*));
+}
+
+/// Asserts that the left expression is equal to the right one when Info level assertions are
+/// enabled.
+///
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_eq;
+/// # fn main() {
+///     iassert_eq!(1 + 1, 3, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_eq {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, ==; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, ==, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Info, ==; $($arg)*));
+}
+
+/// Asserts that the left expression is not equal to the right one when Info level assertions are
+/// enabled.
+///
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_ne;
+/// # fn main() {
+///     iassert_ne!(1 + 1, 2, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_ne {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, !=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, !=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Info, !=; $($arg)*));
+}
+
+/// Asserts that the left expression is less than the right one when Info level assertions are
+/// enabled.
+///
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_lt;
+/// # fn main() {
+///     iassert_lt!(3, 2, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_lt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, <; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, <, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Info, <; $($arg)*));
+}
+
+/// Asserts that the left expression is less than or equal to the right one when Info level
+/// assertions are enabled.
+///
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_le;
+/// # fn main() {
+///     iassert_le!(3, 2, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_le {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, <=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, <=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Info, <=; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than the right one when Info level assertions are
+/// enabled.
+///
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_gt;
+/// # fn main() {
+///     iassert_gt!(2, 3, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_gt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, >; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, >, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Info, >; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than or equal to the right one when Info level
+/// assertions are enabled.
+///
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_ge;
+/// # fn main() {
+///     iassert_ge!(2, 3, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_ge {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, >=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Info, >=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Info, >=; $($arg)*));
+}
+
+/// Asserts that the left expression is equal to the right one when Debug level assertions are
+/// enabled.
+///
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_eq;
+/// # fn main() {
+///     dassert_eq!(1 + 1, 3, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_eq {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, ==; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, ==, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, ==; $($arg)*));
+}
+
+/// Asserts that the left expression is not equal to the right one when Debug level assertions are
+/// enabled.
+///
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_ne;
+/// # fn main() {
+///     dassert_ne!(1 + 1, 2, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_ne {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, !=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, !=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, !=; $($arg)*));
+}
+
+/// Asserts that the left expression is less than the right one when Debug level assertions are
+/// enabled.
+///
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_lt;
+/// # fn main() {
+///     dassert_lt!(3, 2, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_lt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, <; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, <, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, <; $($arg)*));
+}
+
+/// Asserts that the left expression is less than or equal to the right one when Debug level
+/// assertions are enabled.
+///
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_le;
+/// # fn main() {
+///     dassert_le!(3, 2, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_le {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, <=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, <=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, <=; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than the right one when Debug level assertions are
+/// enabled.
+///
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_gt;
+/// # fn main() {
+///     dassert_gt!(2, 3, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_gt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, >; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, >, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, >; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than or equal to the right one when Debug level
+/// assertions are enabled.
+///
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_ge;
+/// # fn main() {
+///     dassert_ge!(2, 3, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_ge {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, >=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, >=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Debug, >=; $($arg)*));
+}
+
+/// Asserts that the left expression is equal to the right one when Trace level assertions are
+/// enabled.
+///
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_eq;
+/// # fn main() {
+///     tassert_eq!(1 + 1, 3, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_eq {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, ==; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, ==, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, ==; $($arg)*));
+}
+
+/// Asserts that the left expression is not equal to the right one when Trace level assertions are
+/// enabled.
+///
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_ne;
+/// # fn main() {
+///     tassert_ne!(1 + 1, 2, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_ne {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, !=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, !=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, !=; $($arg)*));
+}
+
+/// Asserts that the left expression is less than the right one when Trace level assertions are
+/// enabled.
+///
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_lt;
+/// # fn main() {
+///     tassert_lt!(3, 2, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_lt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, <; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, <, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, <; $($arg)*));
+}
+
+/// Asserts that the left expression is less than or equal to the right one when Trace level
+/// assertions are enabled.
+///
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_le;
+/// # fn main() {
+///     tassert_le!(3, 2, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_le {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, <=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, <=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, <=; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than the right one when Trace level assertions are
+/// enabled.
+///
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_gt;
+/// # fn main() {
+///     tassert_gt!(2, 3, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_gt {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, >; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, >, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, >; $($arg)*));
+}
+
+/// Asserts that the left expression is greater than or equal to the right one when Trace level
+/// assertions are enabled.
+///
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_ge;
+/// # fn main() {
+///     tassert_ge!(2, 3, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_ge {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, >=; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, >=, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_cmp_at_level!($crate::AssertLevel::Trace, >=; $($arg)*));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::level_lock;
+    use crate::{AssertConfig, AssertLevel};
+
+    fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let payload = std::panic::catch_unwind(f).unwrap_err();
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn comparisons_pass() {
+        let _lock = level_lock();
+        eassert_eq!(2 + 2, 4);
+        wassert_ne!(2 + 2, 5,);
+        iassert_lt!(1, 2);
+        dassert_le!(2, 2, "{} <= {}", 2, 2);
+        tassert_gt!("b", "a");
+        tassert_ge!(vec![1, 2], vec![1, 2]);
+    }
+
+    #[test]
+    fn comparison_failure_reports_operands() {
+        let _lock = level_lock();
+        let message = panic_message(|| dassert_eq!(2 + 2, 5));
+        assert_eq!(
+            message,
+            "assertion `left == right` failed\n  left: 4\n right: 5"
+        );
+        let message = panic_message(|| tassert_lt!(3, 2, "{} items", 3));
+        assert_eq!(
+            message,
+            "assertion `left < right` failed: 3 items\n  left: 3\n right: 2"
+        );
+    }
+
+    #[test]
+    fn equality_failure_shows_diff() {
+        let _lock = level_lock();
+        let message = panic_message(|| tassert_eq!(vec![1, 2, 3], vec![1, 4, 3]));
+        assert_eq!(
+            message,
+            "assertion `left == right` failed\n  diff (-left +right):\n  [\n      1,\n-     2,\n+     4,\n      3,\n  ]"
+        );
+        let message = panic_message(|| tassert_ne!(vec![1], vec![1]));
+        assert_eq!(
+            message,
+            "assertion `left != right` failed\n  left: [1]\n right: [1]"
+        );
+    }
+
+    #[test]
+    fn comparisons_respect_levels() {
+        let _lock = level_lock();
+        let config = AssertConfig::new(AssertLevel::Warn);
+        iassert_eq!(config; 1, 2);
+        let _guard = crate::scoped_level(AssertLevel::Info);
+        dassert_ne!(1, 1);
+        assert!(std::panic::catch_unwind(|| wassert_eq!(config; 1, 2)).is_err());
+    }
+}
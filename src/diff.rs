@@ -0,0 +1,106 @@
+//! Line diffs of `Debug` representations, for failed equality assertions.
+
+use std::fmt::Write;
+
+/// The largest number of line pairs compared with the full LCS algorithm. Larger inputs fall
+/// back to printing all differing lines as removed and added.
+const MAX_LCS_CELLS: usize = 1 << 22;
+
+/// Returns a line diff of `left` and `right`, with `-` marking lines only in `left` and `+`
+/// marking lines only in `right`.
+pub(crate) fn diff_lines(left: &str, right: &str) -> String {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let prefix = left.iter().zip(&right).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let left_middle = &left[prefix..left.len() - suffix];
+    let right_middle = &right[prefix..right.len() - suffix];
+
+    let mut out = String::new();
+    for line in &left[..prefix] {
+        push_line(&mut out, ' ', line);
+    }
+    if left_middle.len().saturating_mul(right_middle.len()) <= MAX_LCS_CELLS {
+        lcs_diff(&mut out, left_middle, right_middle);
+    } else {
+        left_middle
+            .iter()
+            .for_each(|line| push_line(&mut out, '-', line));
+        right_middle
+            .iter()
+            .for_each(|line| push_line(&mut out, '+', line));
+    }
+    for line in &left[left.len() - suffix..] {
+        push_line(&mut out, ' ', line);
+    }
+    out
+}
+
+fn lcs_diff(out: &mut String, left: &[&str], right: &[&str]) {
+    // lengths[i][j] is the length of the longest common subsequence of left[i..] and right[j..].
+    let width = right.len() + 1;
+    let mut lengths = vec![0usize; (left.len() + 1) * width];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lengths[i * width + j] = if left[i] == right[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            push_line(out, ' ', left[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            push_line(out, '-', left[i]);
+            i += 1;
+        } else {
+            push_line(out, '+', right[j]);
+            j += 1;
+        }
+    }
+    left[i..].iter().for_each(|line| push_line(out, '-', line));
+    right[j..].iter().for_each(|line| push_line(out, '+', line));
+}
+
+fn push_line(out: &mut String, marker: char, line: &str) {
+    let _ = writeln!(out, "{} {}", marker, line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        let left = "a\nb\nc\nd";
+        let right = "a\nc\nx\nd";
+        assert_eq!(diff_lines(left, right), "  a\n- b\n  c\n+ x\n  d\n");
+    }
+
+    #[test]
+    fn diff_of_pretty_debug() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        let left = format!("{:#?}", Point { x: 1, y: 2 });
+        let right = format!("{:#?}", Point { x: 1, y: 3 });
+        assert_eq!(
+            diff_lines(&left, &right),
+            "  Point {\n      x: 1,\n-     y: 2,\n+     y: 3,\n  }\n"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Configuring assert levels from the environment.
+//!
+//! The [`ENV_VAR`] environment variable holds a comma separated list of directives, for example
+//! `INVARIANTS_LEVEL=debug,my_crate::tree=trace`. A bare level sets [`max_level()`], and
+//! `module=level` directives set the per-module levels.
+//!
+//! With the `env_auto_init` feature, the variable is read lazily the first time any level is read
+//! or changed, so no code changes are needed to use it. Levels set in code afterwards still take
+//! precedence. An invalid variable is then ignored, with a warning logged through `log`.
+//!
+//! [`max_level()`]: crate::max_level
+
+use std::sync::atomic::Ordering;
+
+use crate::filter::{self, ParseDirectiveError};
+
+/// The environment variable read by [`init_from_env`].
+pub const ENV_VAR: &str = "INVARIANTS_LEVEL";
+
+/// Sets the assert levels from the [`ENV_VAR`] environment variable.
+///
+/// Does nothing if the variable is not set. On error, the current levels are left untouched.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{init_from_env, max_level, module_level, AssertLevel};
+///
+/// fn main() {
+///     std::env::set_var("INVARIANTS_LEVEL", "debug,my_crate::tree=trace");
+///     init_from_env().unwrap();
+///     assert_eq!(max_level(), AssertLevel::Debug);
+///     assert_eq!(module_level("my_crate::tree"), Some(AssertLevel::Trace));
+/// }
+/// ```
+pub fn init_from_env() -> Result<(), ParseDirectiveError> {
+    init_from_env_var(ENV_VAR)
+}
+
+/// Sets the assert levels from the environment variable `name`.
+///
+/// See [`init_from_env`].
+pub fn init_from_env_var(name: &str) -> Result<(), ParseDirectiveError> {
+    ensure_init();
+    match std::env::var(name) {
+        Ok(spec) => apply(&spec),
+        Err(std::env::VarError::NotPresent) => Ok(()),
+        Err(std::env::VarError::NotUnicode(spec)) => Err(ParseDirectiveError::new(
+            spec.to_string_lossy().into_owned(),
+            "not valid unicode",
+        )),
+    }
+}
+
+fn apply(spec: &str) -> Result<(), ParseDirectiveError> {
+    let directives = filter::parse_directives(spec, true)?;
+    if let Some(level) = directives.default {
+        crate::MAX_LEVEL.store(level as usize, Ordering::Relaxed);
+    }
+    filter::store_module_levels(directives.modules);
+    Ok(())
+}
+
+/// Reads [`ENV_VAR`] once, before the first time any level is read or changed.
+#[cfg(feature = "env_auto_init")]
+#[inline]
+pub(crate) fn ensure_init() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        if let Ok(spec) = std::env::var(ENV_VAR) {
+            if let Err(err) = apply(&spec) {
+                log::warn!("ignoring {}: {}", ENV_VAR, err);
+            }
+        }
+    });
+}
+
+#[cfg(not(feature = "env_auto_init"))]
+#[inline(always)]
+pub(crate) fn ensure_init() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::level_lock;
+    use crate::{max_level, module_level, AssertLevel};
+
+    #[test]
+    fn apply_sets_levels() {
+        let _lock = level_lock();
+        apply("info, invariants::env=off").unwrap();
+        assert_eq!(max_level(), AssertLevel::Info);
+        assert_eq!(module_level(module_path!()), Some(AssertLevel::Off));
+        crate::iassert!(2 + 2 == 5);
+    }
+
+    #[test]
+    fn apply_errors_leave_levels() {
+        let _lock = level_lock();
+        assert!(apply("debug,a=loud").is_err());
+        assert_eq!(max_level(), AssertLevel::Trace);
+        assert_eq!(module_level("a"), None);
+    }
+
+    // Setting variables is not safe while other tests run, so only an unset one is read here.
+    #[test]
+    fn init_from_unset_env_var_leaves_levels() {
+        let _lock = level_lock();
+        assert!(init_from_env_var("INVARIANTS_UNSET_LEVEL").is_ok());
+        assert_eq!(max_level(), AssertLevel::Trace);
+    }
+}
@@ -0,0 +1,551 @@
+//! Reporting failed assertions.
+//!
+//! Every failed assertion is described by a [`Failure`] and passed to the failure handler. The
+//! default handler follows the [`FailurePolicy`] of the failure's level, which is to panic like
+//! `assert!` unless changed with [`set_failure_policy`]. Use [`set_failure_handler`] to report
+//! failures differently, for example with one of the built-in [`handlers`].
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::AssertLevel;
+
+type Handler = Arc<dyn Fn(&Failure) + Send + Sync>;
+
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+/// What the default failure handler does with a failed assertion.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FailurePolicy {
+    /// Panic, like `assert!`.
+    #[default]
+    Panic,
+    /// Log the failure with `log`, at the level of the assertion, and continue.
+    Log,
+    /// Log the failure, then panic.
+    LogAndPanic,
+}
+
+impl FailurePolicy {
+    const fn from_u8(policy: u8) -> Self {
+        match policy {
+            0 => FailurePolicy::Panic,
+            1 => FailurePolicy::Log,
+            _ => FailurePolicy::LogAndPanic,
+        }
+    }
+}
+
+static POLICIES: [AtomicU8; 6] = [const { AtomicU8::new(FailurePolicy::Panic as u8) }; 6];
+
+/// Sets what the default failure handler does with failed assertions of `level`.
+///
+/// Failures of [`cassert!`](crate::cassert) follow the policy of [`AssertLevel::Error`]. The
+/// policy is ignored when a custom handler is set with [`set_failure_handler`].
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{dassert, eassert, set_failure_policy, AssertLevel, FailurePolicy};
+///
+/// fn main() {
+///     set_failure_policy(AssertLevel::Debug, FailurePolicy::Log);
+///     set_failure_policy(AssertLevel::Trace, FailurePolicy::Log);
+///     dassert!(1 + 1 == 3, "This is only logged");
+///     eassert!(1 + 1 == 2, "But this would still panic");
+/// }
+/// ```
+pub fn set_failure_policy(level: AssertLevel, policy: FailurePolicy) {
+    POLICIES[level as usize].store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns what the default failure handler does with failed assertions of `level`.
+pub fn failure_policy(level: AssertLevel) -> FailurePolicy {
+    FailurePolicy::from_u8(POLICIES[level as usize].load(Ordering::Relaxed))
+}
+
+/// The source location of an assertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    file: &'static str,
+    line: u32,
+    column: u32,
+    module_path: &'static str,
+}
+
+impl Location {
+    #[doc(hidden)]
+    pub const fn __new(
+        file: &'static str,
+        line: u32,
+        column: u32,
+        module_path: &'static str,
+    ) -> Self {
+        Self {
+            file,
+            line,
+            column,
+            module_path,
+        }
+    }
+
+    /// Returns the file of the assertion.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// Returns the line of the assertion.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns the column of the assertion.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// Returns the module path of the assertion.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __location {
+    () => {
+        $crate::Location::__new(file!(), line!(), column!(), module_path!())
+    };
+}
+
+/// The operands of a failed comparison assertion, like [`tassert_eq!`](crate::tassert_eq), or
+/// the value and pattern of a failed [`tassert_matches!`](crate::tassert_matches).
+#[derive(Debug, Clone, Copy)]
+pub struct Comparison<'a> {
+    op: &'static str,
+    left: &'a dyn fmt::Debug,
+    right: &'a dyn fmt::Debug,
+}
+
+impl<'a> Comparison<'a> {
+    /// Returns the comparison operator, like `==`, or `matches` for pattern assertions.
+    pub fn op(&self) -> &'static str {
+        self.op
+    }
+
+    /// Returns the left operand.
+    pub fn left(&self) -> &'a dyn fmt::Debug {
+        self.left
+    }
+
+    /// Returns the right operand.
+    pub fn right(&self) -> &'a dyn fmt::Debug {
+        self.right
+    }
+
+    /// Returns a line diff of the pretty printed operands of a failed `==` comparison.
+    ///
+    /// Returns `None` for other comparisons, and when both operands fit on a single line. Lines
+    /// only in the left operand start with `-`, and lines only in the right one start with `+`.
+    pub fn diff(&self) -> Option<String> {
+        if self.op != "==" {
+            return None;
+        }
+        let left = format!("{:#?}", self.left);
+        let right = format!("{:#?}", self.right);
+        if !left.contains('\n') && !right.contains('\n') {
+            return None;
+        }
+        Some(crate::diff::diff_lines(&left, &right))
+    }
+}
+
+/// A failed assertion.
+#[derive(Debug, Clone, Copy)]
+pub struct Failure<'a> {
+    level: Option<AssertLevel>,
+    category: Option<&'static str>,
+    expression: &'static str,
+    location: Location,
+    message: Option<fmt::Arguments<'a>>,
+    comparison: Option<Comparison<'a>>,
+}
+
+impl<'a> Failure<'a> {
+    #[doc(hidden)]
+    pub fn __new(
+        level: Option<AssertLevel>,
+        category: Option<&'static str>,
+        expression: &'static str,
+        location: Location,
+        message: Option<fmt::Arguments<'a>>,
+    ) -> Self {
+        Self {
+            level,
+            category,
+            expression,
+            location,
+            message,
+            comparison: None,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __with_comparison(
+        mut self,
+        op: &'static str,
+        left: &'a dyn fmt::Debug,
+        right: &'a dyn fmt::Debug,
+    ) -> Self {
+        self.comparison = Some(Comparison { op, left, right });
+        self
+    }
+
+    /// Returns the level of the assertion, or `None` for a [`cassert!`](crate::cassert).
+    pub fn level(&self) -> Option<AssertLevel> {
+        self.level
+    }
+
+    /// Returns the name of the category of a [`cassert!`](crate::cassert).
+    pub fn category(&self) -> Option<&'static str> {
+        self.category
+    }
+
+    /// Returns the text of the asserted expression.
+    pub fn expression(&self) -> &'static str {
+        self.expression
+    }
+
+    /// Returns the custom message of the assertion, if one was given.
+    pub fn message(&self) -> Option<&fmt::Arguments<'a>> {
+        self.message.as_ref()
+    }
+
+    /// Returns the operands of a failed comparison assertion.
+    pub fn comparison(&self) -> Option<&Comparison<'a>> {
+        self.comparison.as_ref()
+    }
+
+    /// Returns the location of the assertion.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Returns the file of the assertion.
+    pub fn file(&self) -> &'static str {
+        self.location.file
+    }
+
+    /// Returns the line of the assertion.
+    pub fn line(&self) -> u32 {
+        self.location.line
+    }
+}
+
+/// Formats the failure the same way `assert!` and `assert_eq!` format their panic messages.
+impl fmt::Display for Failure<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.comparison, self.message) {
+            (Some(comparison), _) => {
+                write!(
+                    f,
+                    "assertion `left {} right` failed{}",
+                    comparison.op,
+                    Details(self)
+                )
+            }
+            (None, Some(message)) => write!(f, "{}", message),
+            (None, None) => write!(f, "assertion failed: {}", self.expression),
+        }
+    }
+}
+
+/// Formats the message and the operands of a failure, without the failed expression.
+struct Details<'a, 'b>(&'a Failure<'b>);
+
+impl fmt::Display for Details<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(message) = self.0.message {
+            write!(f, ": {}", message)?;
+        }
+        if let Some(comparison) = self.0.comparison {
+            match comparison.diff() {
+                Some(diff) => write!(f, "\n  diff (-left +right):\n{}", diff.trim_end())?,
+                None => write!(
+                    f,
+                    "\n  left: {:?}\n right: {:?}",
+                    comparison.left, comparison.right
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets the handler called for every failed assertion, replacing the previous one.
+///
+/// The handler may be called concurrently from multiple threads.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{eassert, set_failure_handler};
+///
+/// fn main() {
+///     set_failure_handler(Box::new(|failure| {
+///         eprintln!("{} failed at {}", failure.expression(), failure.location());
+///     }));
+///     eassert!(1 + 1 == 3);
+/// }
+/// ```
+pub fn set_failure_handler(handler: Box<dyn Fn(&Failure) + Send + Sync>) {
+    *HANDLER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::from(handler));
+}
+
+/// Restores the default failure handler, which follows the [`FailurePolicy`] of each level.
+pub fn reset_failure_handler() {
+    *HANDLER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check {
+    (@site $callsite:ident; $level:expr, $category:expr; $cond:expr $(,)?) => ({
+        $callsite.__evaluated();
+        if $cond {
+            $callsite.__passed();
+        } else {
+            $callsite.__failed();
+            $crate::__private::fail(&$crate::Failure::__new(
+                $level,
+                $category,
+                stringify!($cond),
+                $crate::__location!(),
+                None,
+            ));
+        }
+    });
+    (@site $callsite:ident; $level:expr, $category:expr; $cond:expr, $($arg:tt)+) => ({
+        $callsite.__evaluated();
+        if $cond {
+            $callsite.__passed();
+        } else {
+            $callsite.__failed();
+            $crate::__private::fail(&$crate::Failure::__new(
+                $level,
+                $category,
+                stringify!($cond),
+                $crate::__location!(),
+                Some(format_args!($($arg)+)),
+            ));
+        }
+    });
+    ($level:expr, $category:expr; $cond:expr $(,)?) => (
+        if !$cond {
+            $crate::__private::fail(&$crate::Failure::__new(
+                $level,
+                $category,
+                stringify!($cond),
+                $crate::__location!(),
+                None,
+            ));
+        }
+    );
+    ($level:expr, $category:expr; $cond:expr, $($arg:tt)+) => (
+        if !$cond {
+            $crate::__private::fail(&$crate::Failure::__new(
+                $level,
+                $category,
+                stringify!($cond),
+                $crate::__location!(),
+                Some(format_args!($($arg)+)),
+            ));
+        }
+    );
+}
+
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn fail(failure: &Failure) {
+    let handler = HANDLER.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match handler {
+        Some(handler) => handler(failure),
+        None => handlers::policy(failure),
+    }
+}
+
+/// Built-in failure handlers.
+pub mod handlers {
+    use super::*;
+
+    /// Handles the failure according to the [`FailurePolicy`] of its level. This is the default
+    /// handler.
+    #[track_caller]
+    pub fn policy(failure: &Failure) {
+        let level = failure.level().unwrap_or(AssertLevel::Error);
+        match failure_policy(level) {
+            FailurePolicy::Panic => panic(failure),
+            FailurePolicy::Log => log(failure),
+            FailurePolicy::LogAndPanic => {
+                log(failure);
+                panic(failure);
+            }
+        }
+    }
+
+    /// Panics with the failure message, like `assert!`.
+    ///
+    /// When no handler is set, the panic points at the assertion. When this handler is installed
+    /// explicitly with [`set_failure_handler`], the panic points into this crate instead.
+    #[track_caller]
+    pub fn panic(failure: &Failure) {
+        panic!("{}", failure)
+    }
+
+    /// Logs the failure with `log` and continues.
+    ///
+    /// Failures of leveled assertions are logged at their level, and category failures at the
+    /// error level.
+    pub fn log(failure: &Failure) {
+        let level = failure
+            .level()
+            .and_then(AssertLevel::to_log_level)
+            .unwrap_or(::log::Level::Error);
+        ::log::log!(
+            target: failure.location().module_path(),
+            level,
+            "assertion `{}` failed at {}{}",
+            failure.expression(),
+            failure.location(),
+            Details(failure)
+        );
+    }
+
+    /// Prints the failure to stderr and aborts the process.
+    pub fn abort(failure: &Failure) {
+        eprintln!("{} at {}", failure, failure.location());
+        std::process::abort();
+    }
+
+    /// Counts failures and continues.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use invariants::handlers::FailureCounter;
+    /// use invariants::{eassert, set_failure_handler};
+    ///
+    /// fn main() {
+    ///     let counter = FailureCounter::new();
+    ///     set_failure_handler(Box::new(counter.handler()));
+    ///     eassert!(1 + 1 == 3);
+    ///     assert_eq!(counter.count(), 1);
+    /// }
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct FailureCounter {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl FailureCounter {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Returns the number of failures counted so far.
+        pub fn count(&self) -> usize {
+            self.count.load(Ordering::Relaxed)
+        }
+
+        /// Resets the count to zero, returning the previous count.
+        pub fn reset(&self) -> usize {
+            self.count.swap(0, Ordering::Relaxed)
+        }
+
+        /// Returns a handler that increments this counter.
+        pub fn handler(&self) -> impl Fn(&Failure) + Send + Sync + 'static {
+            let count = self.count.clone();
+            move |_| {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::handlers::FailureCounter;
+    use super::*;
+    use crate::tests::level_lock;
+    use crate::{cassert, eassert, invariant_category, tassert};
+
+    #[test]
+    fn handler_receives_failure() {
+        let _lock = level_lock();
+        let failures = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = failures.clone();
+        set_failure_handler(Box::new(move |failure| {
+            sink.lock().unwrap().push((
+                failure.level(),
+                failure.expression(),
+                failure.to_string(),
+                failure.line(),
+            ));
+        }));
+        let line = line!() + 1;
+        tassert!(1 + 1 == 3, "{} is not {}", 1 + 1, 3);
+        eassert!(true);
+        assert_eq!(
+            *failures.lock().unwrap(),
+            vec![(
+                Some(AssertLevel::Trace),
+                "1 + 1 == 3",
+                "2 is not 3".to_string(),
+                line
+            )]
+        );
+    }
+
+    #[test]
+    fn counter_counts_category_failures() {
+        invariant_category!(COUNTED = enabled);
+        let _lock = level_lock();
+        let counter = FailureCounter::new();
+        set_failure_handler(Box::new(counter.handler()));
+        cassert!(COUNTED; false);
+        eassert!(false,);
+        assert_eq!(counter.reset(), 2);
+        assert_eq!(counter.count(), 0);
+    }
+
+    #[test]
+    fn policy_logs_instead_of_panicking() {
+        let _lock = level_lock();
+        set_failure_policy(AssertLevel::Trace, FailurePolicy::Log);
+        tassert!(1 + 1 == 3);
+        set_failure_policy(AssertLevel::Trace, FailurePolicy::LogAndPanic);
+        assert!(std::panic::catch_unwind(|| tassert!(1 + 1 == 3)).is_err());
+        assert!(std::panic::catch_unwind(|| eassert!(1 + 1 == 3)).is_err());
+        assert_eq!(failure_policy(AssertLevel::Debug), FailurePolicy::Panic);
+    }
+
+    #[test]
+    fn default_handler_panics_like_assert() {
+        let _lock = level_lock();
+        let result = std::panic::catch_unwind(|| eassert!(1 + 1 == 3));
+        let payload = result.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<String>().map(String::as_str),
+            Some("assertion failed: 1 + 1 == 3")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Per-module assertion levels.
+//!
+//! Directives look like `env_logger` directives: `mycrate::hashset=trace,mycrate::net=warn`. An
+//! assertion whose `module_path!()` is equal to, or nested in, a directive's module uses that
+//! directive's level instead of [`max_level()`](crate::max_level). When several directives match,
+//! the longest module path wins.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::RwLock;
+
+use crate::AssertLevel;
+
+static MODULE_LEVELS: RwLock<Vec<(String, AssertLevel)>> = RwLock::new(Vec::new());
+
+/// The error returned when a directive string can not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectiveError {
+    directive: String,
+    reason: &'static str,
+}
+
+impl ParseDirectiveError {
+    pub(crate) fn new(directive: String, reason: &'static str) -> Self {
+        Self { directive, reason }
+    }
+}
+
+impl fmt::Display for ParseDirectiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid directive `{}`: {}", self.directive, self.reason)
+    }
+}
+
+impl Error for ParseDirectiveError {}
+
+/// The result of parsing a comma separated list of directives.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Directives {
+    pub(crate) default: Option<AssertLevel>,
+    pub(crate) modules: Vec<(String, AssertLevel)>,
+}
+
+/// Parses `spec` into directives. A bare level, without a module, is only accepted when
+/// `allow_default` is set.
+pub(crate) fn parse_directives(
+    spec: &str,
+    allow_default: bool,
+) -> Result<Directives, ParseDirectiveError> {
+    let mut directives = Directives::default();
+    for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let error = |reason| ParseDirectiveError::new(directive.to_string(), reason);
+        match directive.split_once('=') {
+            Some((module, level)) => {
+                let module = module.trim();
+                if module.is_empty() {
+                    return Err(error("missing module path"));
+                }
+                let level = level.trim().parse().map_err(|_| error("unknown level"))?;
+                directives.modules.push((module.to_string(), level));
+            }
+            None if allow_default => {
+                let level = directive.parse().map_err(|_| error("unknown level"))?;
+                directives.default = Some(level);
+            }
+            None => return Err(error("expected `module=level`")),
+        }
+    }
+    Ok(directives)
+}
+
+/// Replaces all per-module levels with the directives in `spec`.
+///
+/// `spec` is a comma separated list of `module=level` directives. On error, the current levels
+/// are left untouched.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{module_level, set_module_levels, AssertLevel};
+///
+/// fn main() {
+///     set_module_levels("mycrate::hashset=trace,mycrate::net=warn").unwrap();
+///     assert_eq!(module_level("mycrate::hashset::raw"), Some(AssertLevel::Trace));
+///     assert_eq!(module_level("mycrate::network"), None);
+/// }
+/// ```
+pub fn set_module_levels(spec: &str) -> Result<(), ParseDirectiveError> {
+    let directives = parse_directives(spec, false)?;
+    replace_module_levels(directives.modules);
+    Ok(())
+}
+
+/// Removes all per-module levels, so every module uses [`max_level()`](crate::max_level) again.
+pub fn clear_module_levels() {
+    replace_module_levels(Vec::new());
+}
+
+fn replace_module_levels(modules: Vec<(String, AssertLevel)>) {
+    crate::env::ensure_init();
+    store_module_levels(modules);
+}
+
+pub(crate) fn store_module_levels(modules: Vec<(String, AssertLevel)>) {
+    *MODULE_LEVELS.write().unwrap_or_else(|e| e.into_inner()) = modules;
+    crate::callsite::invalidate_all();
+}
+
+/// Returns the level of the longest directive matching `module`, if any.
+pub fn module_level(module: &str) -> Option<AssertLevel> {
+    crate::env::ensure_init();
+    let levels = MODULE_LEVELS.read().unwrap_or_else(|e| e.into_inner());
+    levels
+        .iter()
+        .filter(|(prefix, _)| is_module_prefix(prefix, module))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|&(_, level)| level)
+}
+
+fn is_module_prefix(prefix: &str, module: &str) -> bool {
+    match module.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::level_lock;
+    use crate::{iassert, tassert};
+
+    #[test]
+    fn parse_directives_with_default() {
+        let directives = parse_directives(" debug, a::b=trace ,c=off,", true).unwrap();
+        assert_eq!(directives.default, Some(AssertLevel::Debug));
+        assert_eq!(
+            directives.modules,
+            vec![
+                ("a::b".to_string(), AssertLevel::Trace),
+                ("c".to_string(), AssertLevel::Off)
+            ]
+        );
+        assert!(parse_directives("debug", false).is_err());
+        assert!(parse_directives("a=loud", true).is_err());
+        assert!(parse_directives("=warn", true).is_err());
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let _lock = level_lock();
+        set_module_levels("a=warn,a::b=trace").unwrap();
+        assert_eq!(module_level("a"), Some(AssertLevel::Warn));
+        assert_eq!(module_level("a::c"), Some(AssertLevel::Warn));
+        assert_eq!(module_level("a::b::c"), Some(AssertLevel::Trace));
+        assert_eq!(module_level("ab"), None);
+        assert!(set_module_levels("a").is_err());
+        assert_eq!(module_level("a"), Some(AssertLevel::Warn));
+    }
+
+    #[test]
+    fn module_level_filters_call_sites() {
+        let _lock = level_lock();
+        set_module_levels(&format!("{}=warn", module_path!())).unwrap();
+        iassert!(2 + 2 == 5);
+        clear_module_levels();
+        let result = std::panic::catch_unwind(|| iassert!(2 + 2 == 5));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn module_level_overrides_max_level() {
+        let _lock = level_lock();
+        let _guard = crate::scoped_level(AssertLevel::Warn);
+        set_module_levels(&format!("{}=trace", module_path!())).unwrap();
+        let result = std::panic::catch_unwind(|| tassert!(2 + 2 == 5));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,504 @@
+//! Invariants of whole values.
+//!
+//! The [`Invariant`] trait checks the invariant of a value and returns the first violated
+//! constraint. It is implemented for the std containers by checking their elements, so the
+//! `eassert_invariant!`..`tassert_invariant!` macros can check a whole object graph at once. Like
+//! the assertions, they accept optional `id = "name";` and `config;` prefixes.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{AssertConfig, AssertLevel, InvariantViolation};
+
+/// A type with an invariant that can be checked at runtime.
+///
+/// With the `macros` feature, it can be derived from constraints on the fields, written as
+/// `#[inv(...)]` attributes, see the derive macro of the same name.
+///
+/// Containers and smart pointers implement it by checking their elements, maps both their keys
+/// and their values, and primitives and strings implement it with an invariant that always holds,
+/// so they can be used as fields.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{tassert_invariant, tcheck, AssertConfig, Invariant, InvariantViolation};
+///
+/// struct Range {
+///     start: u32,
+///     end: u32,
+/// }
+///
+/// impl Invariant for Range {
+///     fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+///         tcheck!(config; self.start <= self.end)
+///     }
+/// }
+///
+/// # fn main() {
+/// tassert_invariant!(vec![Range { start: 1, end: 2 }]);
+/// # }
+/// ```
+pub trait Invariant {
+    /// Checks the constraints of levels up to the level of `config`, and returns the first one
+    /// violated.
+    ///
+    /// Implementations should pass `config` on to the checks of nested values.
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation>;
+
+    /// Checks the constraints of levels up to `level`.
+    fn check_invariant(&self, level: AssertLevel) -> Result<(), InvariantViolation> {
+        self.check(&AssertConfig::new(level))
+    }
+
+    /// Returns whether all the constraints hold.
+    fn holds(&self) -> bool {
+        self.check_invariant(AssertLevel::Trace).is_ok()
+    }
+}
+
+macro_rules! impl_trivial {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Invariant for $ty {
+                fn check(&self, _config: &AssertConfig) -> Result<(), InvariantViolation> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_trivial!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    str,
+    String,
+);
+
+macro_rules! impl_deref {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<T: Invariant + ?Sized> Invariant for $ty {
+                fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+                    (**self).check(config)
+                }
+            }
+        )*
+    };
+}
+
+impl_deref!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+macro_rules! impl_iter {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<T: Invariant> Invariant for $ty {
+                fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+                    self.iter().try_for_each(|item| item.check(config))
+                }
+            }
+        )*
+    };
+}
+
+impl_iter!(
+    [T],
+    Vec<T>,
+    VecDeque<T>,
+    LinkedList<T>,
+    BTreeSet<T>,
+    BinaryHeap<T>,
+    Option<T>,
+);
+
+impl<T: Invariant, const N: usize> Invariant for [T; N] {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.as_slice().check(config)
+    }
+}
+
+impl<T: Invariant, S> Invariant for HashSet<T, S> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.iter().try_for_each(|item| item.check(config))
+    }
+}
+
+impl<K: Invariant, V: Invariant, S> Invariant for HashMap<K, V, S> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.iter().try_for_each(|(key, value)| {
+            key.check(config)?;
+            value.check(config)
+        })
+    }
+}
+
+impl<K: Invariant, V: Invariant> Invariant for BTreeMap<K, V> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.iter().try_for_each(|(key, value)| {
+            key.check(config)?;
+            value.check(config)
+        })
+    }
+}
+
+impl<T: Invariant, E: Invariant> Invariant for Result<T, E> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        match self {
+            Ok(value) => value.check(config),
+            Err(error) => error.check(config),
+        }
+    }
+}
+
+impl<T: Invariant + Copy> Invariant for Cell<T> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.get().check(config)
+    }
+}
+
+/// Values that are already mutably borrowed are not checked.
+impl<T: Invariant + ?Sized> Invariant for RefCell<T> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.try_borrow()
+            .map_or(Ok(()), |value| value.check(config))
+    }
+}
+
+/// Values that are locked by another thread, or by the current one, are not checked.
+impl<T: Invariant + ?Sized> Invariant for Mutex<T> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.try_lock().map_or(Ok(()), |value| value.check(config))
+    }
+}
+
+/// Values that are locked for writing are not checked.
+impl<T: Invariant + ?Sized> Invariant for RwLock<T> {
+    fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+        self.try_read().map_or(Ok(()), |value| value.check(config))
+    }
+}
+
+macro_rules! impl_tuple {
+    ($(($($name:ident),+)),* $(,)?) => {
+        $(
+            impl<$($name: Invariant),+> Invariant for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+                    let ($($name,)+) = self;
+                    $($name.check(config)?;)+
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple!((A), (A, B), (A, B, C), (A, B, C, D));
+
+#[doc(hidden)]
+pub fn is_sorted<'a, T: PartialOrd + 'a>(items: impl IntoIterator<Item = &'a T>) -> bool {
+    let mut items = items.into_iter();
+    let Some(mut prev) = items.next() else {
+        return true;
+    };
+    items.all(|item| {
+        let sorted = prev <= item;
+        prev = item;
+        sorted
+    })
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_invariant {
+    ($level:expr; $value:expr $(,)?) => {
+        if let ::core::result::Result::Err(violation) =
+            $crate::Invariant::check(&$value, &$crate::AssertConfig::new($level))
+        {
+            $crate::__private::fail(&$crate::Failure::__new(
+                Some($level),
+                None,
+                stringify!($value),
+                $crate::__location!(),
+                Some(format_args!(
+                    "invariant of `{}` violated: {}",
+                    stringify!($value),
+                    violation
+                )),
+            ));
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_invariant_at_level {
+    (@site $id:expr, $level:expr $(, $config:expr)?; $($arg:tt)*) => (
+        if $crate::__site_enabled!($level, $crate::__condition!($($arg)*), $id)
+            $(&& $level <= $config.assertion_level())?
+        {
+            $crate::__assert_invariant!($level; $($arg)*);
+        }
+    );
+    ($level:expr; id = $id:literal; $config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!(@site Some($id), $level, $config; $($arg)*)
+    );
+    ($level:expr; id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!(@site Some($id), $level; $($arg)*)
+    );
+    ($level:expr, $config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!(@site None, $level, $config; $($arg)*)
+    );
+    ($level:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!(@site None, $level; $($arg)*)
+    );
+}
+
+/// Asserts that the [`Invariant`](trait@crate::Invariant) of the given value holds when Error level
+/// assertions are enabled.
+///
+/// The value is checked with an [`AssertConfig`](crate::AssertConfig) of the Error level, so only
+/// the constraints of levels up to Error are checked, also in nested values. See
+/// [`eassert!`](crate::eassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::{eassert_invariant, AssertConfig, Invariant, InvariantViolation};
+///
+/// struct Even(u32);
+///
+/// impl Invariant for Even {
+///     fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+///         invariants::echeck!(config; self.0 % 2 == 0)
+///     }
+/// }
+///
+/// # fn main() {
+///     eassert_invariant!(vec![Some(Even(2)), None, Some(Even(3))]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_invariant {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Error; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Error, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_invariant_at_level!($crate::AssertLevel::Error; $($arg)*));
+}
+
+/// Asserts that the [`Invariant`](trait@crate::Invariant) of the given value holds when Warn level
+/// assertions are enabled.
+///
+/// The value is checked with an [`AssertConfig`](crate::AssertConfig) of the Warn level, so only
+/// the constraints of levels up to Warn are checked, also in nested values. See
+/// [`wassert!`](crate::wassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::{wassert_invariant, AssertConfig, Invariant, InvariantViolation};
+///
+/// struct Even(u32);
+///
+/// impl Invariant for Even {
+///     fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+///         invariants::wcheck!(config; self.0 % 2 == 0)
+///     }
+/// }
+///
+/// # fn main() {
+///     wassert_invariant!(vec![Some(Even(2)), None, Some(Even(3))]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_invariant {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Warn; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Warn, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_invariant_at_level!($crate::AssertLevel::Warn; $($arg)*));
+}
+
+/// Asserts that the [`Invariant`](trait@crate::Invariant) of the given value holds when Info level
+/// assertions are enabled.
+///
+/// The value is checked with an [`AssertConfig`](crate::AssertConfig) of the Info level, so only
+/// the constraints of levels up to Info are checked, also in nested values. See
+/// [`iassert!`](crate::iassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::{iassert_invariant, AssertConfig, Invariant, InvariantViolation};
+///
+/// struct Even(u32);
+///
+/// impl Invariant for Even {
+///     fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+///         invariants::icheck!(config; self.0 % 2 == 0)
+///     }
+/// }
+///
+/// # fn main() {
+///     iassert_invariant!(vec![Some(Even(2)), None, Some(Even(3))]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_invariant {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Info; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Info, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_invariant_at_level!($crate::AssertLevel::Info; $($arg)*));
+}
+
+/// Asserts that the [`Invariant`](trait@crate::Invariant) of the given value holds when Debug level
+/// assertions are enabled.
+///
+/// The value is checked with an [`AssertConfig`](crate::AssertConfig) of the Debug level, so only
+/// the constraints of levels up to Debug are checked, also in nested values. See
+/// [`dassert!`](crate::dassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::{dassert_invariant, AssertConfig, Invariant, InvariantViolation};
+///
+/// struct Even(u32);
+///
+/// impl Invariant for Even {
+///     fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+///         invariants::dcheck!(config; self.0 % 2 == 0)
+///     }
+/// }
+///
+/// # fn main() {
+///     dassert_invariant!(vec![Some(Even(2)), None, Some(Even(3))]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_invariant {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Debug; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Debug, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_invariant_at_level!($crate::AssertLevel::Debug; $($arg)*));
+}
+
+/// Asserts that the [`Invariant`](trait@crate::Invariant) of the given value holds when Trace level
+/// assertions are enabled.
+///
+/// The value is checked with an [`AssertConfig`](crate::AssertConfig) of the Trace level, so only
+/// the constraints of levels up to Trace are checked, also in nested values. See
+/// [`tassert!`](crate::tassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::{tassert_invariant, AssertConfig, Invariant, InvariantViolation};
+///
+/// struct Even(u32);
+///
+/// impl Invariant for Even {
+///     fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+///         invariants::tcheck!(config; self.0 % 2 == 0)
+///     }
+/// }
+///
+/// # fn main() {
+///     tassert_invariant!(vec![Some(Even(2)), None, Some(Even(3))]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_invariant {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Trace; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_invariant_at_level!($crate::AssertLevel::Trace, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_invariant_at_level!($crate::AssertLevel::Trace; $($arg)*));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dcheck;
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Even(u32);
+
+    impl Invariant for Even {
+        fn check(&self, config: &AssertConfig) -> Result<(), InvariantViolation> {
+            dcheck!(config; self.0 % 2 == 0, "{} is odd", self.0)
+        }
+    }
+
+    #[test]
+    fn is_sorted_allows_duplicates() {
+        assert!(is_sorted(&Vec::<u32>::new()));
+        assert!(is_sorted(&[1, 1, 2]));
+        assert!(!is_sorted(&[2, 1]));
+        assert!(is_sorted(&BTreeSet::from([3, 1, 2])));
+    }
+
+    #[test]
+    fn containers_check_their_elements() {
+        let _lock = crate::tests::level_lock();
+        let mut map = HashMap::new();
+        map.insert("a", vec![Some(Box::new(Even(2))), None]);
+        assert!(map.holds());
+        map.insert("b", vec![Some(Box::new(Even(3)))]);
+        assert_eq!(
+            map.check_invariant(AssertLevel::Trace)
+                .unwrap_err()
+                .message(),
+            "3 is odd"
+        );
+        assert_eq!(map.check_invariant(AssertLevel::Info), Ok(()));
+        assert!((1, Rc::new(RefCell::new(Even(4)))).holds());
+        let keys = BTreeMap::from([(Even(2), "a"), (Even(5), "b")]);
+        assert_eq!(
+            keys.check_invariant(AssertLevel::Trace)
+                .unwrap_err()
+                .message(),
+            "5 is odd"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invariant of `values` violated: 1 is odd at ")]
+    fn assert_invariant_fails() {
+        let _lock = crate::tests::level_lock();
+        let values = [Even(0), Even(1)];
+        iassert_invariant!(values);
+        dassert_invariant!(values);
+    }
+}
@@ -0,0 +1,170 @@
+//! The assertion level type.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The level of an assertion.
+///
+/// Levels are ordered from `Off`, which disables all assertions, to `Trace`, which enables all of
+/// them. An assertion runs when its level is lower than or equal to the enabled level.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssertLevel {
+    /// Disables all assertions. No assertion has this level.
+    Off,
+    /// The cheapest checks, checked by [`eassert!`](crate::eassert).
+    Error,
+    /// Checked by [`wassert!`](crate::wassert).
+    Warn,
+    /// Checked by [`iassert!`](crate::iassert).
+    Info,
+    /// Checked by [`dassert!`](crate::dassert).
+    Debug,
+    /// The most expensive checks, checked by [`tassert!`](crate::tassert).
+    Trace,
+}
+
+const NAMES: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+impl AssertLevel {
+    const ALL: [AssertLevel; 6] = [
+        AssertLevel::Off,
+        AssertLevel::Error,
+        AssertLevel::Warn,
+        AssertLevel::Info,
+        AssertLevel::Debug,
+        AssertLevel::Trace,
+    ];
+
+    /// Returns the most verbose level.
+    pub const fn max() -> Self {
+        AssertLevel::Trace
+    }
+
+    /// Returns an iterator over all levels, from `Off` to `Trace`.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter()
+    }
+
+    /// Returns the lowercase name of this level, as accepted by [`FromStr`].
+    pub fn as_str(&self) -> &'static str {
+        NAMES[*self as usize]
+    }
+
+    pub(crate) const fn from_usize(level: usize) -> Self {
+        Self::ALL[level]
+    }
+
+    /// Converts a `log` level filter to the matching assert level.
+    pub const fn from_log_filter(filter: log::LevelFilter) -> Self {
+        match filter {
+            log::LevelFilter::Off => AssertLevel::Off,
+            log::LevelFilter::Error => AssertLevel::Error,
+            log::LevelFilter::Warn => AssertLevel::Warn,
+            log::LevelFilter::Info => AssertLevel::Info,
+            log::LevelFilter::Debug => AssertLevel::Debug,
+            log::LevelFilter::Trace => AssertLevel::Trace,
+        }
+    }
+
+    /// Returns the `log` level filter matching this level.
+    pub const fn to_log_filter(self) -> log::LevelFilter {
+        match self {
+            AssertLevel::Off => log::LevelFilter::Off,
+            AssertLevel::Error => log::LevelFilter::Error,
+            AssertLevel::Warn => log::LevelFilter::Warn,
+            AssertLevel::Info => log::LevelFilter::Info,
+            AssertLevel::Debug => log::LevelFilter::Debug,
+            AssertLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+
+    /// Returns the `log` level matching this level, or `None` for `Off`.
+    pub const fn to_log_level(self) -> Option<log::Level> {
+        match self {
+            AssertLevel::Off => None,
+            AssertLevel::Error => Some(log::Level::Error),
+            AssertLevel::Warn => Some(log::Level::Warn),
+            AssertLevel::Info => Some(log::Level::Info),
+            AssertLevel::Debug => Some(log::Level::Debug),
+            AssertLevel::Trace => Some(log::Level::Trace),
+        }
+    }
+}
+
+impl From<log::Level> for AssertLevel {
+    fn from(level: log::Level) -> Self {
+        Self::from_log_filter(level.to_level_filter())
+    }
+}
+
+impl From<log::LevelFilter> for AssertLevel {
+    fn from(filter: log::LevelFilter) -> Self {
+        Self::from_log_filter(filter)
+    }
+}
+
+impl From<AssertLevel> for log::LevelFilter {
+    fn from(level: AssertLevel) -> Self {
+        level.to_log_filter()
+    }
+}
+
+impl fmt::Display for AssertLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// The error returned when parsing an unknown [`AssertLevel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError(());
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown assert level, expected one of {}",
+            NAMES.join(", ")
+        )
+    }
+}
+
+impl Error for ParseLevelError {}
+
+impl FromStr for AssertLevel {
+    type Err = ParseLevelError;
+
+    /// Parses a level name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|level| level.as_str().eq_ignore_ascii_case(s))
+            .ok_or(ParseLevelError(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for level in AssertLevel::iter() {
+            assert_eq!(level.to_string().parse(), Ok(level));
+        }
+        assert_eq!("TRACE".parse(), Ok(AssertLevel::Trace));
+        assert!("paranoid".parse::<AssertLevel>().is_err());
+        assert_eq!(format!("{:>5}", AssertLevel::Warn), " warn");
+    }
+
+    #[test]
+    fn log_conversions() {
+        for filter in log::LevelFilter::iter() {
+            assert_eq!(log::LevelFilter::from(AssertLevel::from(filter)), filter);
+            assert_eq!(AssertLevel::from(filter).to_log_level(), filter.to_level());
+        }
+        assert_eq!(AssertLevel::from(log::Level::Warn), AssertLevel::Warn);
+        assert!(AssertLevel::Off < AssertLevel::Error && AssertLevel::Debug < AssertLevel::Trace);
+    }
+}
@@ -10,7 +10,7 @@ all assertions in release mode and only *some* in tests, by your choosing.
 ## Basic Usage
 There are different assertion levels defined, and macros are provided for each of them.
 For trace assertions, you can use the `tassert!` macro:
-```rust
+```rust,should_panic
 use invariants::tassert;
 fn main() {
     tassert!(false, "This will fail when assert level is equal or lower then {}. Current level is {}.",
@@ -18,20 +18,144 @@ fn main() {
 }
 ```
 
+## Assertion levels
+Whether an assertion runs is decided by the following rules, in order. Apart from the module
+directives of rule 2, each one can only turn an assertion off, never back on:
+1. [`STATIC_MAX_LEVEL`], the compile time ceiling. Assertions above it are compiled out.
+   It is set with this crate's cargo features, see [`STATIC_MAX_LEVEL`].
+2. The runtime level of the assertion's module. It is [`max_level()`], the global runtime level
+   shared by all threads, unless a per-module directive set with [`set_module_levels`] or
+   [`ENV_VAR`] matches the assertion's `module_path!()`. The longest matching directive then
+   replaces the global level for that module, so with `debug,my_crate::tree=trace`, trace
+   assertions run in `my_crate::tree` even though the global level is `debug`.
+3. [`thread_level()`], an optional override for the current thread, set with
+   [`scoped_thread_level`] or [`with_thread_level`].
+4. The [`AssertConfig`] passed to the macro, if any.
+
+With the `macros` feature, which is enabled by default, [`macro@invariant`] checks a struct
+invariant around every public `&mut self` method of an `impl` block, and [`macro@requires`] and
+[`macro@ensures`] check the preconditions and postconditions of a function. Invariants shared by
+all the implementors of a trait are declared with [`macro@trait_invariant`], which adds an
+extension trait with checked calls of the trait's methods for every implementor. The
+[`Invariant`](trait@Invariant) trait can be derived from constraints on the fields of a struct.
+
+The `eassert_invariant!`..`tassert_invariant!` macros check the [`Invariant`](trait@Invariant) of
+a value, and of all the values it contains, at their level.
+
+Loop invariants are checked with [`loop_invariant!`] in the loop body. With
+[`macro@loop_invariants`] on the function, they are also checked on entry to and exit from the
+loop.
+
+For code that must not panic, the `echeck!`..`tcheck!` macros return an [`InvariantViolation`]
+instead.
+
+Failed assertions panic by default, like `assert!`. Use [`set_failure_policy`] to log the
+failures of some levels instead, or [`set_failure_handler`] to report them differently, see
+[`handlers`].
+
+Independently of the levels, assertions can be grouped into named categories that are enabled on
+their own, see [`invariant_category!`] and [`cassert!`].
+
+The global and per-module levels can also be set from the `INVARIANTS_LEVEL` environment variable,
+see [`init_from_env`].
+
+[`sites`] lists every assertion call site in the binary with its level, location and condition,
+collected at link time with the default `static_sites` feature. Without it, call sites register
+themselves the first time they are reached. Single sites can be switched off by location or ID with
+[`set_site_enabled`], without changing the levels. With the `stats` feature, the `stats` module
+counts how often every `eassert!`..`tassert!` call site was reached, evaluated, passed and failed.
+
 See the github repository for more information.
 */
 
-pub type AssertLevel = log::LevelFilter;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod callsite;
+mod category;
+mod cmp;
+mod diff;
+mod env;
+mod failure;
+mod filter;
+mod invariant;
+mod level;
+mod loops;
+mod matches;
+#[cfg(feature = "stats")]
+pub mod stats;
+mod unreachable;
+mod violation;
 
-pub const STATIC_MAX_LEVEL: AssertLevel = log::STATIC_MAX_LEVEL;
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "static_sites")]
+    pub use crate::callsite::CALLSITES;
+    pub use crate::failure::fail;
+    pub use crate::invariant::is_sorted;
+    pub use crate::matches::Pattern;
+    pub use crate::unreachable::unreachable_unchecked;
+    #[cfg(feature = "static_sites")]
+    pub use linkme;
+}
+
+pub use callsite::{set_site_enabled, sites, Callsite};
+pub use category::Category;
+pub use env::{init_from_env, init_from_env_var, ENV_VAR};
+pub use failure::{
+    failure_policy, handlers, reset_failure_handler, set_failure_handler, set_failure_policy,
+    Comparison, Failure, FailurePolicy, Location,
+};
+pub use filter::{clear_module_levels, module_level, set_module_levels, ParseDirectiveError};
+pub use invariant::Invariant;
+pub use level::{AssertLevel, ParseLevelError};
+pub use violation::InvariantViolation;
+
+#[cfg(feature = "macros")]
+pub use invariants_macros::{
+    ensures, invariant, loop_invariants, requires, trait_invariant, Invariant,
+};
+
+/// The compile time ceiling of the assert level.
+///
+/// Assertions above this level are compiled out. It defaults to `Trace`, and can be lowered with
+/// the `max_level_*` features, or the `release_max_level_*` features for builds without
+/// `debug_assertions`. These are independent of the `log` crate's features, so quieting logging
+/// does not disable assertions.
+///
+/// With the `log_max_level` feature, the ceiling is also capped at [`log::STATIC_MAX_LEVEL`], which
+/// was the behavior of earlier versions of this crate.
+pub const STATIC_MAX_LEVEL: AssertLevel = if cfg!(feature = "log_max_level")
+    && (log::STATIC_MAX_LEVEL as usize) < (CRATE_MAX_LEVEL as usize)
+{
+    AssertLevel::from_log_filter(log::STATIC_MAX_LEVEL)
+} else {
+    CRATE_MAX_LEVEL
+};
+
+const CRATE_MAX_LEVEL: AssertLevel = match cfg!(debug_assertions) {
+    false if cfg!(feature = "release_max_level_off") => AssertLevel::Off,
+    false if cfg!(feature = "release_max_level_error") => AssertLevel::Error,
+    false if cfg!(feature = "release_max_level_warn") => AssertLevel::Warn,
+    false if cfg!(feature = "release_max_level_info") => AssertLevel::Info,
+    false if cfg!(feature = "release_max_level_debug") => AssertLevel::Debug,
+    false if cfg!(feature = "release_max_level_trace") => AssertLevel::Trace,
+    _ if cfg!(feature = "max_level_off") => AssertLevel::Off,
+    _ if cfg!(feature = "max_level_error") => AssertLevel::Error,
+    _ if cfg!(feature = "max_level_warn") => AssertLevel::Warn,
+    _ if cfg!(feature = "max_level_info") => AssertLevel::Info,
+    _ if cfg!(feature = "max_level_debug") => AssertLevel::Debug,
+    _ => AssertLevel::Trace,
+};
 
-static mut MAX_LEVEL: AssertLevel = AssertLevel::Trace;
+static MAX_LEVEL: AtomicUsize = AtomicUsize::new(AssertLevel::Trace as usize);
 
 /// Sets the max assert level. This level is checked in runtime.
 ///
-/// # Safety
-/// This function is unsafe because it can lead to undefined behavior if called from multiple threads
-/// without synchronization.
+/// This function is safe to call from multiple threads, but the level is global, so concurrent
+/// callers will overwrite each other. Use [`compare_exchange_max_level`] to change the level only
+/// if nobody else changed it in the meantime.
 ///
 /// # Examples
 ///
@@ -44,14 +168,213 @@ static mut MAX_LEVEL: AssertLevel = AssertLevel::Trace;
 /// }
 /// ```
 pub fn set_max_level(level: AssertLevel) {
-    unsafe {
-        MAX_LEVEL = level;
-    }
+    env::ensure_init();
+    MAX_LEVEL.store(level as usize, Ordering::Relaxed);
+    callsite::invalidate_all();
 }
 
 /// Returns the max assert level. This level is checked in runtime.
 pub fn max_level() -> AssertLevel {
-    unsafe { MAX_LEVEL }
+    env::ensure_init();
+    AssertLevel::from_usize(MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Sets the max assert level to `new` if it is currently `current`.
+///
+/// Returns the previous level on success, and the actual current level on failure. This lets a
+/// test harness bump the level without clobbering a level set by someone else.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{compare_exchange_max_level, max_level, AssertLevel};
+///
+/// fn main() {
+///     let current = max_level();
+///     assert_eq!(compare_exchange_max_level(current, AssertLevel::Debug), Ok(current));
+///     assert_eq!(compare_exchange_max_level(current, AssertLevel::Info), Err(AssertLevel::Debug));
+/// }
+/// ```
+pub fn compare_exchange_max_level(
+    current: AssertLevel,
+    new: AssertLevel,
+) -> Result<AssertLevel, AssertLevel> {
+    env::ensure_init();
+    let result = MAX_LEVEL
+        .compare_exchange(
+            current as usize,
+            new as usize,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        )
+        .map(AssertLevel::from_usize)
+        .map_err(AssertLevel::from_usize);
+    if result.is_ok() {
+        callsite::invalidate_all();
+    }
+    result
+}
+
+/// Restores the previous max assert level when dropped.
+///
+/// Returned by [`scoped_level`].
+#[must_use = "the previous level is restored as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct LevelGuard {
+    previous: AssertLevel,
+}
+
+impl LevelGuard {
+    /// Returns the level that will be restored when this guard is dropped.
+    pub fn previous(&self) -> AssertLevel {
+        self.previous
+    }
+}
+
+impl Drop for LevelGuard {
+    fn drop(&mut self) {
+        set_max_level(self.previous);
+    }
+}
+
+/// Sets the max assert level until the returned guard is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{max_level, scoped_level, AssertLevel};
+///
+/// fn main() {
+///     let before = max_level();
+///     {
+///         let _guard = scoped_level(AssertLevel::Error);
+///         assert_eq!(max_level(), AssertLevel::Error);
+///     }
+///     assert_eq!(max_level(), before);
+/// }
+/// ```
+pub fn scoped_level(level: AssertLevel) -> LevelGuard {
+    env::ensure_init();
+    let previous = AssertLevel::from_usize(MAX_LEVEL.swap(level as usize, Ordering::Relaxed));
+    callsite::invalidate_all();
+    LevelGuard { previous }
+}
+
+/// Runs `f` with the max assert level set to `level`, restoring the previous level afterwards.
+///
+/// The previous level is restored even if `f` panics.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{tassert, with_level, AssertLevel};
+///
+/// fn main() {
+///     with_level(AssertLevel::Debug, || {
+///         tassert!(false, "Trace asserts are off in here");
+///     });
+/// }
+/// ```
+pub fn with_level<R>(level: AssertLevel, f: impl FnOnce() -> R) -> R {
+    let _guard = scoped_level(level);
+    f()
+}
+
+thread_local! {
+    static THREAD_LEVEL: Cell<Option<AssertLevel>> = const { Cell::new(None) };
+}
+
+/// Returns the assert level override of the current thread, if one is set.
+///
+/// The override can only lower the level further than the runtime level of a module, which is
+/// [`max_level()`] or the level of a module directive. It never enables assertions that level
+/// disabled.
+pub fn thread_level() -> Option<AssertLevel> {
+    THREAD_LEVEL.with(Cell::get)
+}
+
+/// Sets or clears the assert level override of the current thread.
+///
+/// Prefer [`scoped_thread_level`] or [`with_thread_level`], which restore the previous override.
+pub fn set_thread_level(level: Option<AssertLevel>) {
+    THREAD_LEVEL.with(|cell| cell.set(level));
+}
+
+/// Restores the previous thread level override when dropped.
+///
+/// Returned by [`scoped_thread_level`]. The guard must be dropped on the thread that created it.
+#[must_use = "the previous thread level is restored as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ThreadLevelGuard {
+    previous: Option<AssertLevel>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ThreadLevelGuard {
+    /// Returns the override that will be restored when this guard is dropped.
+    pub fn previous(&self) -> Option<AssertLevel> {
+        self.previous
+    }
+}
+
+impl Drop for ThreadLevelGuard {
+    fn drop(&mut self) {
+        set_thread_level(self.previous);
+    }
+}
+
+/// Overrides the assert level of the current thread until the returned guard is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{scoped_thread_level, thread_level, AssertLevel};
+///
+/// fn main() {
+///     {
+///         let _guard = scoped_thread_level(AssertLevel::Warn);
+///         assert_eq!(thread_level(), Some(AssertLevel::Warn));
+///     }
+///     assert_eq!(thread_level(), None);
+/// }
+/// ```
+pub fn scoped_thread_level(level: AssertLevel) -> ThreadLevelGuard {
+    let previous = THREAD_LEVEL.with(|cell| cell.replace(Some(level)));
+    ThreadLevelGuard {
+        previous,
+        _not_send: PhantomData,
+    }
+}
+
+/// Runs `f` with the assert level of the current thread overridden to `level`.
+///
+/// The previous override is restored even if `f` panics.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{dassert, with_thread_level, AssertLevel};
+///
+/// fn main() {
+///     with_thread_level(AssertLevel::Info, || {
+///         dassert!(false, "Debug asserts are off on this thread");
+///     });
+/// }
+/// ```
+pub fn with_thread_level<R>(level: AssertLevel, f: impl FnOnce() -> R) -> R {
+    let _guard = scoped_thread_level(level);
+    f()
+}
+
+/// Returns whether assertions of `level` are enabled on the current thread.
+///
+/// This checks [`STATIC_MAX_LEVEL`], [`max_level()`] and [`thread_level()`], but neither per-module
+/// levels nor any [`AssertConfig`]. Use [`assert_enabled!`] to also take the module of the caller
+/// into account.
+pub fn enabled(level: AssertLevel) -> bool {
+    level <= STATIC_MAX_LEVEL
+        && level <= max_level()
+        && thread_level().map_or(true, |thread| level <= thread)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -69,15 +392,112 @@ impl AssertConfig {
     }
 }
 
+/// Returns whether assertions of the given level are enabled at the call site.
+///
+/// Unlike [`enabled`], this also takes the per-module levels into account, and caches the lookup
+/// the same way the assertion macros do.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{assert_enabled, tassert, AssertLevel};
+///
+/// # fn main() {
+/// if assert_enabled!(AssertLevel::Trace) {
+///     let expensive = (0..1000).sum::<u32>();
+///     tassert!(expensive == 499500);
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_enabled {
+    ($level:expr) => {{
+        $crate::__callsite_static!(
+            __CALLSITE = $crate::Callsite::__new(None, None, "", $crate::__location!())
+        );
+        let level: $crate::AssertLevel = $level;
+        level <= $crate::STATIC_MAX_LEVEL && __CALLSITE.enabled(level)
+    }};
+}
+
+/// Like [`assert_enabled!`], but for an assertion of a constant level, whose call site is
+/// registered with its level and condition.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __site_enabled {
+    (@site $callsite:ident, $level:expr) => {{
+        $callsite.__reached();
+        $level <= $crate::STATIC_MAX_LEVEL && $callsite.enabled($level)
+    }};
+    ($level:expr, $condition:expr) => {
+        $crate::__site_enabled!($level, $condition, None)
+    };
+    ($level:expr, $condition:expr, $id:expr) => {{
+        $crate::__callsite_static!(
+            __CALLSITE =
+                $crate::Callsite::__new(Some($level), $id, $condition, $crate::__location!())
+        );
+        $level <= $crate::STATIC_MAX_LEVEL && __CALLSITE.enabled($level)
+    }};
+}
+
+/// Returns the text of the condition of an assertion's arguments.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __condition {
+    ($cond:expr $(, $($arg:tt)*)?) => {
+        stringify!($cond)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_at_level {
+    (@site $id:expr, $level:expr $(, $config:expr)?; $($arg:tt)*) => ({
+        $crate::__callsite_static!(
+            __CALLSITE = $crate::Callsite::__new(
+                Some($level),
+                $id,
+                $crate::__condition!($($arg)*),
+                $crate::__location!(),
+            )
+        );
+        $crate::__assert_at_level!(@callsite __CALLSITE, $level $(, $config)?; $($arg)*)
+    });
+    // Checks the assertion at an existing call site.
+    (@callsite $callsite:ident, $level:expr $(, $config:expr)?; $($arg:tt)*) => (
+        if $crate::__site_enabled!(@site $callsite, $level)
+            $(&& $level <= $config.assertion_level())?
+        {
+            $crate::__check!(@site $callsite; Some($level), None; $($arg)*);
+        }
+    );
+    ($level:expr; id = $id:literal; $config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!(@site Some($id), $level, $config; $($arg)*)
+    );
+    ($level:expr; id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_at_level!(@site Some($id), $level; $($arg)*)
+    );
+    ($level:expr, $config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!(@site None, $level, $config; $($arg)*)
+    );
+    ($level:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!(@site None, $level; $($arg)*)
+    );
+}
+
 /// Asserts that the given expression is true when Error level assertions are enabled.
 ///
 /// If the max assert level is lower than Error, this macro does nothing.
 /// If the max and current assert level is equal or higher then Error, this macro panics if the expression is
 /// evaluated to false.
 ///
+/// An `id = "name";` prefix, before the optional config, names the call site so it can be
+/// switched off with [`set_site_enabled`].
+///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,should_panic
 /// use invariants::eassert;
 /// # fn main() {
 ///     eassert!(false, "This will fail when assert level is equal or lower then {}. Current level is {}. Max compile time level is {}.",
@@ -86,12 +506,13 @@ impl AssertConfig {
 /// ```
 #[macro_export]
 macro_rules! eassert {
-    ($config:expr; $($arg:tt)*) =>(
-        if $crate::AssertLevel::Error <= $crate::STATIC_MAX_LEVEL
-            && $crate::AssertLevel::Error <= $crate::max_level()
-            && $crate::AssertLevel::Error <= $config.assertion_level()  { assert!($($arg)*); });
-    ($($arg:tt)*) => (if $crate::AssertLevel::Error <= $crate::STATIC_MAX_LEVEL
-                        && $crate::AssertLevel::Error <= $crate::max_level() { assert!($($arg)*); });
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Error; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Error, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_at_level!($crate::AssertLevel::Error; $($arg)*));
 }
 
 /// Asserts that the given expression is true when Warn level assertions are enabled.
@@ -100,9 +521,12 @@ macro_rules! eassert {
 /// If the max and current assert level is equal or higher then Warn, this macro panics if the expression is
 /// evaluated to false.
 ///
+/// An `id = "name";` prefix, before the optional config, names the call site so it can be
+/// switched off with [`set_site_enabled`].
+///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,should_panic
 /// use invariants::wassert;
 /// # fn main() {
 ///     wassert!(false, "This will fail when assert level is equal or lower then {}. Current level is {}.",
@@ -111,12 +535,13 @@ macro_rules! eassert {
 /// ```
 #[macro_export]
 macro_rules! wassert {
-    ($config:expr; $($arg:tt)*) =>(
-        if $crate::AssertLevel::Warn <= $crate::STATIC_MAX_LEVEL
-            && $crate::AssertLevel::Warn <= $crate::max_level()
-            && $crate::AssertLevel::Warn <= $config.assertion_level()  { assert!($($arg)*); });
-    ($($arg:tt)*) => (if $crate::AssertLevel::Warn <= $crate::STATIC_MAX_LEVEL
-                        && $crate::AssertLevel::Warn <= $crate::max_level() { assert!($($arg)*); })
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Warn; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Warn, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_at_level!($crate::AssertLevel::Warn; $($arg)*));
 }
 
 /// Asserts that the given expression is true when Info level assertions are enabled.
@@ -125,9 +550,12 @@ macro_rules! wassert {
 /// If the max and current assert level is equal or higher then Info, this macro panics if the expression is
 /// evaluated to false.
 ///
+/// An `id = "name";` prefix, before the optional config, names the call site so it can be
+/// switched off with [`set_site_enabled`].
+///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,should_panic
 /// use invariants::iassert;
 /// # fn main() {
 ///     iassert!(false, "This will fail when assert level is equal or lower then {}. Current level is {}.",
@@ -136,12 +564,13 @@ macro_rules! wassert {
 /// ```
 #[macro_export]
 macro_rules! iassert {
-    ($config:expr; $($arg:tt)*) =>(
-        if $crate::AssertLevel::Info <= $crate::STATIC_MAX_LEVEL
-            && $crate::AssertLevel::Info <= $crate::max_level()
-            && $crate::AssertLevel::Info <= $config.assertion_level()  { assert!($($arg)*); });
-    ($($arg:tt)*) => (if $crate::AssertLevel::Info <= $crate::STATIC_MAX_LEVEL
-                        && $crate::AssertLevel::Info <= $crate::max_level() { assert!($($arg)*); })
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Info; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Info, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_at_level!($crate::AssertLevel::Info; $($arg)*));
 }
 
 /// Asserts that the given expression is true when Debug level assertions are enabled.
@@ -150,9 +579,12 @@ macro_rules! iassert {
 /// If the max and current assert level is equal or higher then Debug, this macro panics if the expression is
 /// evaluated to false.
 ///
+/// An `id = "name";` prefix, before the optional config, names the call site so it can be
+/// switched off with [`set_site_enabled`].
+///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,should_panic
 /// use invariants::dassert;
 /// # fn main() {
 ///     dassert!(false, "This will fail when assert level is equal or lower then {}. Current level is {}.",
@@ -161,12 +593,13 @@ macro_rules! iassert {
 /// ```
 #[macro_export]
 macro_rules! dassert {
-    ($config:expr; $($arg:tt)*) =>(
-        if $crate::AssertLevel::Debug <= $crate::STATIC_MAX_LEVEL
-            && $crate::AssertLevel::Debug <= $crate::max_level()
-            && $crate::AssertLevel::Debug <= $config.assertion_level()  { assert!($($arg)*); });
-    ($($arg:tt)*) => (if $crate::AssertLevel::Debug <= $crate::STATIC_MAX_LEVEL
-                        && $crate::AssertLevel::Debug <= $crate::max_level() { assert!($($arg)*); })
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Debug; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Debug, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_at_level!($crate::AssertLevel::Debug; $($arg)*));
 }
 
 /// Asserts that the given expression is true when Trace level assertions are enabled.
@@ -175,9 +608,12 @@ macro_rules! dassert {
 /// If the max and current assert level is equal or higher then Trace, this macro panics if the expression is
 /// evaluated to false.
 ///
+/// An `id = "name";` prefix, before the optional config, names the call site so it can be
+/// switched off with [`set_site_enabled`].
+///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,should_panic
 /// use invariants::tassert;
 /// # fn main() {
 ///     tassert!(false, "This will fail when assert level is equal or lower then {}. Current level is {}.",
@@ -186,17 +622,33 @@ macro_rules! dassert {
 /// ```
 #[macro_export]
 macro_rules! tassert {
-    ($config:expr; $($arg:tt)*) =>(
-        if $crate::AssertLevel::Trace <= $crate::STATIC_MAX_LEVEL
-            && $crate::AssertLevel::Trace <= $crate::max_level()
-            && $crate::AssertLevel::Trace <= $config.assertion_level()  { assert!($($arg)*); });
-    ($($arg:tt)*) => (if $crate::AssertLevel::Trace <= $crate::STATIC_MAX_LEVEL
-                        && $crate::AssertLevel::Trace <= $crate::max_level() { assert!($($arg)*); })
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Trace; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_at_level!($crate::AssertLevel::Trace, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_at_level!($crate::AssertLevel::Trace; $($arg)*));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::dassert;
+    use std::sync::{Mutex, MutexGuard};
+
+    static LEVEL_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Serializes tests that depend on the global levels or failure handler, and resets them to
+    /// their defaults.
+    pub(crate) fn level_lock() -> MutexGuard<'static, ()> {
+        let guard = LEVEL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        crate::set_max_level(crate::AssertLevel::Trace);
+        crate::clear_module_levels();
+        crate::reset_failure_handler();
+        for level in crate::AssertLevel::iter() {
+            crate::set_failure_policy(level, crate::FailurePolicy::Panic);
+        }
+        guard
+    }
 
     #[test]
     fn it_works() {
@@ -208,6 +660,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn it_crashes() {
+        let _lock = level_lock();
         let result = 2 + 2;
         dassert!(result == 5);
         log::info!("{}", result);
@@ -226,6 +679,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn config_crashes() {
+        let _lock = level_lock();
         let result = 2 + 2;
         let config = crate::AssertConfig {
             assertion_level: crate::AssertLevel::Warn,
@@ -246,8 +700,9 @@ mod tests {
 
     #[test]
     fn max_level_filters() {
+        let _lock = level_lock();
         let result = 2 + 3;
-        crate::set_max_level(crate::AssertLevel::Warn);
+        let _guard = crate::scoped_level(crate::AssertLevel::Warn);
         iassert!(result == 4);
         log::info!("{}", result);
     }
@@ -255,10 +710,93 @@ mod tests {
     #[test]
     #[should_panic]
     fn max_level_sanity() {
+        let _lock = level_lock();
         let result = 2 + 3;
-        crate::set_max_level(crate::AssertLevel::Error);
+        let _guard = crate::scoped_level(crate::AssertLevel::Error);
         eassert!(result == 4);
         log::info!("{}", result);
     }
-}
 
+    #[test]
+    fn compare_exchange_max_level() {
+        let _lock = level_lock();
+        assert_eq!(
+            crate::compare_exchange_max_level(crate::AssertLevel::Trace, crate::AssertLevel::Info),
+            Ok(crate::AssertLevel::Trace)
+        );
+        assert_eq!(
+            crate::compare_exchange_max_level(crate::AssertLevel::Trace, crate::AssertLevel::Off),
+            Err(crate::AssertLevel::Info)
+        );
+        assert_eq!(crate::max_level(), crate::AssertLevel::Info);
+    }
+
+    #[test]
+    fn set_max_level_from_threads() {
+        let _lock = level_lock();
+        let handles: Vec<_> = crate::AssertLevel::iter()
+            .map(|level| std::thread::spawn(move || crate::set_max_level(level)))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(crate::AssertLevel::iter().any(|level| level == crate::max_level()));
+    }
+
+    #[test]
+    fn scoped_level_restores() {
+        let _lock = level_lock();
+        {
+            let guard = crate::scoped_level(crate::AssertLevel::Warn);
+            assert_eq!(guard.previous(), crate::AssertLevel::Trace);
+            assert_eq!(crate::max_level(), crate::AssertLevel::Warn);
+            let _inner = crate::scoped_level(crate::AssertLevel::Off);
+            assert_eq!(crate::max_level(), crate::AssertLevel::Off);
+        }
+        assert_eq!(crate::max_level(), crate::AssertLevel::Trace);
+    }
+
+    #[test]
+    fn with_level_restores_on_panic() {
+        let _lock = level_lock();
+        let result = std::panic::catch_unwind(|| {
+            crate::with_level(crate::AssertLevel::Debug, || {
+                dassert!(2 + 2 == 5);
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(crate::max_level(), crate::AssertLevel::Trace);
+        assert_eq!(
+            crate::with_level(crate::AssertLevel::Error, crate::max_level),
+            crate::AssertLevel::Error
+        );
+    }
+
+    #[test]
+    fn thread_level_filters() {
+        let _lock = level_lock();
+        let _guard = crate::scoped_thread_level(crate::AssertLevel::Info);
+        dassert!(2 + 2 == 5);
+        assert!(crate::enabled(crate::AssertLevel::Info));
+        assert!(!crate::enabled(crate::AssertLevel::Debug));
+    }
+
+    #[test]
+    fn thread_level_is_per_thread() {
+        let _lock = level_lock();
+        let _guard = crate::scoped_thread_level(crate::AssertLevel::Off);
+        let other = std::thread::spawn(crate::thread_level).join().unwrap();
+        assert_eq!(other, None);
+        assert_eq!(crate::thread_level(), Some(crate::AssertLevel::Off));
+    }
+
+    #[test]
+    fn thread_level_cannot_raise_global() {
+        let _lock = level_lock();
+        let _global = crate::scoped_level(crate::AssertLevel::Warn);
+        crate::with_thread_level(crate::AssertLevel::Trace, || {
+            iassert!(2 + 2 == 5);
+            assert!(!crate::enabled(crate::AssertLevel::Info));
+        });
+    }
+}
@@ -0,0 +1,106 @@
+//! Loop invariants.
+//!
+//! `loop_invariant!(level; cond)` checks a condition at its position in a loop body, so it runs
+//! once per iteration. With the `macros` feature, `#[loop_invariants]` on the enclosing function
+//! also checks the invariants of a loop on entry and on exit, and reports the iteration in which
+//! they were violated.
+
+/// Asserts a loop invariant at the given level, `trace` by default.
+///
+/// `loop_invariant!` means different things depending on whether the enclosing function has the
+/// `#[loop_invariants]` attribute:
+///
+/// - On its own, it is an assertion like [`tassert!`](crate::tassert) at its position in the loop
+///   body. It is checked every time an iteration reaches it, but not after the last iteration,
+///   and the failure message does not tell which iteration it was.
+/// - With `#[loop_invariants]`, the `loop_invariant!` statements at the start of a loop body are
+///   moved out of the body, and checked on entry to the loop, after every iteration, and on exit.
+///   The failure message tells which. As they are checked outside the loop, their conditions can
+///   only use variables declared before the loop, and not the loop's pattern or the body's locals.
+///
+/// Stating the invariant over variables declared before the loop works the same in both.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::loop_invariant;
+///
+/// # fn main() {
+/// let items = [3, 1, 2];
+/// let mut max = 0;
+/// let mut seen = 0;
+/// for &item in &items {
+///     loop_invariant!(debug; items[..seen].iter().all(|&x| x <= max));
+///     // Should be `max = max.max(item)`, which the invariant catches in the third iteration.
+///     max = item;
+///     seen += 1;
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! loop_invariant {
+    (@at [$($when:tt)+] $text:literal $level:ident; $($arg:tt)+) => (
+        $crate::__loop_invariant!($level; [$($when)+] $text, $($arg)+)
+    );
+    ($level:ident; $cond:expr $(, $($arg:tt)+)?) => (
+        $crate::__loop_invariant!($level; [] stringify!($cond), $cond $(, $($arg)+)?)
+    );
+    ($cond:expr $(, $($arg:tt)+)?) => (
+        $crate::__loop_invariant!(trace; [] stringify!($cond), $cond $(, $($arg)+)?)
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __loop_invariant {
+    (error; $($arg:tt)+) => ($crate::__loop_invariant!(@level $crate::AssertLevel::Error; $($arg)+));
+    (warn; $($arg:tt)+) => ($crate::__loop_invariant!(@level $crate::AssertLevel::Warn; $($arg)+));
+    (info; $($arg:tt)+) => ($crate::__loop_invariant!(@level $crate::AssertLevel::Info; $($arg)+));
+    (debug; $($arg:tt)+) => ($crate::__loop_invariant!(@level $crate::AssertLevel::Debug; $($arg)+));
+    (trace; $($arg:tt)+) => ($crate::__loop_invariant!(@level $crate::AssertLevel::Trace; $($arg)+));
+    (@level $level:expr; [] $text:expr, $cond:expr $(,)?) => (
+        $crate::__assert_at_level!($level; $cond, "loop invariant `{}` violated", $text)
+    );
+    (@level $level:expr; [] $text:expr, $cond:expr, $($arg:tt)+) => (
+        $crate::__assert_at_level!(
+            $level;
+            $cond,
+            "loop invariant `{}` violated: {}",
+            $text,
+            format_args!($($arg)+)
+        )
+    );
+    (@level $level:expr; [$($when:tt)+] $text:expr, $cond:expr $(,)?) => (
+        $crate::__assert_at_level!(
+            $level;
+            $cond,
+            "loop invariant `{}` violated {}",
+            $text,
+            format_args!($($when)+)
+        )
+    );
+    (@level $level:expr; [$($when:tt)+] $text:expr, $cond:expr, $($arg:tt)+) => (
+        $crate::__assert_at_level!(
+            $level;
+            $cond,
+            "loop invariant `{}` violated {}: {}",
+            $text,
+            format_args!($($when)+),
+            format_args!($($arg)+)
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[should_panic(expected = "loop invariant `sum <= 3` violated: sum is 6")]
+    fn loop_invariant_is_checked_every_iteration() {
+        let _lock = crate::tests::level_lock();
+        let mut sum = 0;
+        for i in 0..5 {
+            loop_invariant!(info; sum <= 3, "sum is {}", sum);
+            sum += i;
+        }
+    }
+}
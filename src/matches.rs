@@ -0,0 +1,249 @@
+//! Leveled pattern matching assertions.
+//!
+//! Like [`tassert!`](crate::tassert), `tassert_matches!` and friends accept an optional
+//! `id = "name";` prefix followed by an optional `config;` prefix.
+
+use std::fmt;
+
+/// The text of a pattern, printed without quotes by `Debug`.
+#[doc(hidden)]
+pub struct Pattern(pub &'static str);
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __matches {
+    ($level:expr; $value:expr, $pat:pat $(if $guard:expr)? $(, $($arg:tt)*)?) => (
+        match $value {
+            $pat $(if $guard)? => {}
+            ref value => {
+                $crate::__private::fail(
+                    &$crate::Failure::__new(
+                        Some($level),
+                        None,
+                        $crate::__matches_condition!($value, $pat $(if $guard)?),
+                        $crate::__location!(),
+                        $crate::__message!($($($arg)*)?),
+                    )
+                    .__with_comparison(
+                        "matches",
+                        value,
+                        &$crate::__private::Pattern(
+                            concat!(stringify!($pat) $(, " if ", stringify!($guard))?),
+                        ),
+                    ),
+                );
+            }
+        }
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __matches_condition {
+    ($value:expr, $pat:pat $(if $guard:expr)? $(, $($arg:tt)*)?) => {
+        concat!(
+            stringify!($value),
+            " matches ",
+            stringify!($pat)
+            $(, " if ", stringify!($guard))?
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_matches_at_level {
+    (@site $id:expr, $level:expr $(, $config:expr)?; $($arg:tt)*) => (
+        if $crate::__site_enabled!($level, $crate::__matches_condition!($($arg)*), $id)
+            $(&& $level <= $config.assertion_level())?
+        {
+            $crate::__matches!($level; $($arg)*);
+        }
+    );
+    ($level:expr; id = $id:literal; $config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!(@site Some($id), $level, $config; $($arg)*)
+    );
+    ($level:expr; id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!(@site Some($id), $level; $($arg)*)
+    );
+    ($level:expr, $config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!(@site None, $level, $config; $($arg)*)
+    );
+    ($level:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!(@site None, $level; $($arg)*)
+    );
+}
+
+/// Asserts that a value matches a pattern when Error level assertions are enabled.
+///
+/// The pattern may be followed by an `if` guard. On failure, the value is printed with `Debug`.
+/// See [`eassert!`](crate::eassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eassert_matches;
+/// # fn main() {
+///     let value = Some(3);
+///     eassert_matches!(value, Some(x) if x > 5, "This will fail when Error level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eassert_matches {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Error; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Error, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_matches_at_level!($crate::AssertLevel::Error; $($arg)*));
+}
+
+/// Asserts that a value matches a pattern when Warn level assertions are enabled.
+///
+/// The pattern may be followed by an `if` guard. On failure, the value is printed with `Debug`.
+/// See [`wassert!`](crate::wassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wassert_matches;
+/// # fn main() {
+///     let value = Some(3);
+///     wassert_matches!(value, Some(x) if x > 5, "This will fail when Warn level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wassert_matches {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Warn; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Warn, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_matches_at_level!($crate::AssertLevel::Warn; $($arg)*));
+}
+
+/// Asserts that a value matches a pattern when Info level assertions are enabled.
+///
+/// The pattern may be followed by an `if` guard. On failure, the value is printed with `Debug`.
+/// See [`iassert!`](crate::iassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iassert_matches;
+/// # fn main() {
+///     let value = Some(3);
+///     iassert_matches!(value, Some(x) if x > 5, "This will fail when Info level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iassert_matches {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Info; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Info, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_matches_at_level!($crate::AssertLevel::Info; $($arg)*));
+}
+
+/// Asserts that a value matches a pattern when Debug level assertions are enabled.
+///
+/// The pattern may be followed by an `if` guard. On failure, the value is printed with `Debug`.
+/// See [`dassert!`](crate::dassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dassert_matches;
+/// # fn main() {
+///     let value = Some(3);
+///     dassert_matches!(value, Some(x) if x > 5, "This will fail when Debug level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dassert_matches {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Debug; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Debug, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_matches_at_level!($crate::AssertLevel::Debug; $($arg)*));
+}
+
+/// Asserts that a value matches a pattern when Trace level assertions are enabled.
+///
+/// The pattern may be followed by an `if` guard. On failure, the value is printed with `Debug`.
+/// See [`tassert!`](crate::tassert) for when the assertion is checked.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tassert_matches;
+/// # fn main() {
+///     let value = Some(3);
+///     tassert_matches!(value, Some(x) if x > 5, "This will fail when Trace level assertions are enabled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tassert_matches {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Trace; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__assert_matches_at_level!($crate::AssertLevel::Trace, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__assert_matches_at_level!($crate::AssertLevel::Trace; $($arg)*));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::level_lock;
+    use crate::{AssertConfig, AssertLevel};
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum State {
+        Open { fd: u32 },
+        Closed,
+    }
+
+    #[test]
+    fn matches_pass() {
+        let _lock = level_lock();
+        let state = State::Open { fd: 3 };
+        eassert_matches!(state, State::Open { .. });
+        wassert_matches!(&state, State::Open { fd } if *fd > 2, "fd {:?}", state);
+        iassert_matches!(Some(1), Some(1 | 2));
+    }
+
+    #[test]
+    fn matches_failure_prints_value() {
+        let _lock = level_lock();
+        let result = std::panic::catch_unwind(|| {
+            dassert_matches!(State::Closed, State::Open { fd } if fd > 2, "not open");
+        });
+        let payload = result.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<String>().unwrap(),
+            "assertion `left matches right` failed: not open\n  left: Closed\n right: State::Open { fd } if fd > 2"
+        );
+    }
+
+    #[test]
+    fn matches_respect_levels() {
+        let _lock = level_lock();
+        tassert_matches!(AssertConfig::new(AssertLevel::Debug); None::<u8>, Some(_));
+        let _guard = crate::scoped_thread_level(AssertLevel::Warn);
+        iassert_matches!(State::Closed, State::Open { .. });
+    }
+}
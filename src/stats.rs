@@ -0,0 +1,210 @@
+//! Counts of how often every `eassert!`..`tassert!` call site is reached, evaluated, passes and
+//! fails, with the `stats` feature.
+//!
+//! A call site is reached every time the macro runs, and evaluated when its level is enabled, so
+//! that its condition is checked. The counts tell which assertions the tests actually exercise,
+//! and which ones dominate the cost of the checks.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use invariants::{stats, tassert};
+//!
+//! # fn main() {
+//! for i in 0..10 {
+//!     tassert!(i < 10);
+//! }
+//! print!("{}", stats::snapshot());
+//! # }
+//! ```
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::callsite::{sites, Callsite};
+
+/// The counters of a call site.
+#[derive(Debug)]
+pub(crate) struct Counters {
+    pub(crate) reached: AtomicU64,
+    pub(crate) evaluated: AtomicU64,
+    pub(crate) passed: AtomicU64,
+    pub(crate) failed: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            reached: AtomicU64::new(0),
+            evaluated: AtomicU64::new(0),
+            passed: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn add(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        for counter in [&self.reached, &self.evaluated, &self.passed, &self.failed] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The counts of a call site at the time of a [`snapshot`].
+#[derive(Debug, Clone, Copy)]
+pub struct SiteStats {
+    callsite: &'static Callsite,
+    reached: u64,
+    evaluated: u64,
+    passed: u64,
+    failed: u64,
+}
+
+impl SiteStats {
+    /// Returns the call site.
+    pub fn callsite(&self) -> &'static Callsite {
+        self.callsite
+    }
+
+    /// Returns how many times the call site was reached, whether its level was enabled or not.
+    pub fn reached(&self) -> u64 {
+        self.reached
+    }
+
+    /// Returns how many times the condition was evaluated.
+    pub fn evaluated(&self) -> u64 {
+        self.evaluated
+    }
+
+    /// Returns how many times the condition held.
+    pub fn passed(&self) -> u64 {
+        self.passed
+    }
+
+    /// Returns how many times the condition did not hold.
+    pub fn failed(&self) -> u64 {
+        self.failed
+    }
+}
+
+/// The counts of every call site reached so far, most evaluated first.
+///
+/// Its `Display` implementation prints a table of the counts.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    sites: Vec<SiteStats>,
+}
+
+impl Snapshot {
+    /// Returns the counts of the call sites, most evaluated first.
+    pub fn sites(&self) -> &[SiteStats] {
+        &self.sites
+    }
+
+    /// Returns the counts of the call site with the given ID, if it was reached.
+    pub fn get(&self, id: &str) -> Option<&SiteStats> {
+        self.sites
+            .iter()
+            .find(|site| site.callsite.id() == Some(id))
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const HEADERS: [&str; 4] = ["reached", "evaluated", "passed", "failed"];
+        let mut widths = HEADERS.map(str::len);
+        for site in &self.sites {
+            let counts = [site.reached, site.evaluated, site.passed, site.failed];
+            for (width, count) in widths.iter_mut().zip(counts) {
+                *width = (*width).max(count.to_string().len());
+            }
+        }
+        for (header, width) in HEADERS.iter().zip(widths) {
+            write!(f, "{:>width$}  ", header, width = width)?;
+        }
+        writeln!(f, "site")?;
+        for site in &self.sites {
+            let counts = [site.reached, site.evaluated, site.passed, site.failed];
+            for (count, width) in counts.iter().zip(widths) {
+                write!(f, "{:>width$}  ", count, width = width)?;
+            }
+            writeln!(f, "{}", site.callsite)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the counts of every `eassert!`..`tassert!` call site reached so far.
+pub fn snapshot() -> Snapshot {
+    let mut sites: Vec<SiteStats> = sites()
+        .map(|callsite| SiteStats {
+            callsite,
+            reached: callsite.counters.reached.load(Ordering::Relaxed),
+            evaluated: callsite.counters.evaluated.load(Ordering::Relaxed),
+            passed: callsite.counters.passed.load(Ordering::Relaxed),
+            failed: callsite.counters.failed.load(Ordering::Relaxed),
+        })
+        .filter(|site| site.reached > 0)
+        .collect();
+    sites.sort_by_key(|site| {
+        let location = site.callsite.location();
+        (Reverse(site.evaluated), location.file(), location.line())
+    });
+    Snapshot { sites }
+}
+
+/// Sets the counts of every call site back to zero.
+pub fn reset() {
+    for callsite in sites() {
+        callsite.counters.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dassert, set_failure_policy, tassert, AssertLevel, FailurePolicy};
+
+    #[test]
+    fn counts_sites() {
+        let _lock = crate::tests::level_lock();
+        fn check(value: u32) {
+            dassert!(id = "stats.even"; value % 2 == 0);
+            tassert!(id = "stats.small"; value < 10, "value is {}", value);
+        }
+        reset();
+        crate::set_max_level(AssertLevel::Debug);
+        for value in 0..4 {
+            check(value * 2);
+        }
+        crate::set_max_level(AssertLevel::Trace);
+        set_failure_policy(AssertLevel::Debug, FailurePolicy::Log);
+        set_failure_policy(AssertLevel::Trace, FailurePolicy::Log);
+        check(11);
+
+        let counts = |stats: &Snapshot, id| {
+            let site = stats.get(id).unwrap();
+            (
+                site.reached(),
+                site.evaluated(),
+                site.passed(),
+                site.failed(),
+            )
+        };
+        let stats = snapshot();
+        assert_eq!(counts(&stats, "stats.even"), (5, 5, 4, 1));
+        assert_eq!(counts(&stats, "stats.small"), (5, 1, 0, 1));
+        let table = stats.to_string();
+        assert!(table.starts_with("reached  evaluated  passed  failed  site\n"));
+        assert!(table.contains("\n      5          5       4       1  "));
+        assert!(table.contains("[debug] stats.even: value % 2 == 0\n"));
+
+        reset();
+        assert!(snapshot().get("stats.even").is_none());
+    }
+}
@@ -0,0 +1,425 @@
+//! Leveled unreachable and unimplemented markers.
+//!
+//! `tunreachable!()` and friends mark code paths that are believed to be dead. When their level is
+//! enabled, reaching them is a failed assertion, reported through the failure handler. When it is
+//! not, they do nothing.
+//!
+//! The `unchecked` form, like `tunreachable!(unchecked)`, instead tells the compiler the path is
+//! unreachable when the level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL),
+//! using [`std::hint::unreachable_unchecked`]. It must be wrapped in an `unsafe` block, since
+//! reaching it in such a build is undefined behavior. Otherwise it always diverges: it panics after
+//! calling the failure handler when the level is enabled, and panics like [`unreachable!`] when the
+//! level is disabled at runtime, so that the runtime levels and switches never lead to undefined
+//! behavior. It can be used where a value is expected.
+//!
+//! Like the assertions, the markers accept an optional `id = "name";` prefix, before `unchecked`
+//! or the optional `config;` prefix, as in `tunreachable!(id = "parser.eof"; unchecked)`.
+
+/// See [`std::hint::unreachable_unchecked`].
+///
+/// # Safety
+///
+/// Must never be reached.
+#[doc(hidden)]
+#[inline(always)]
+pub unsafe fn unreachable_unchecked() -> ! {
+    unsafe { std::hint::unreachable_unchecked() }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __marker_failure {
+    ($level:expr, unreachable) => (
+        $crate::__marker_failure!(@fail $level, "unreachable!()",
+            format_args!("internal error: entered unreachable code"))
+    );
+    ($level:expr, unreachable, $($arg:tt)+) => (
+        $crate::__marker_failure!(@fail $level, "unreachable!()",
+            format_args!("internal error: entered unreachable code: {}", format_args!($($arg)+)))
+    );
+    ($level:expr, unimplemented) => (
+        $crate::__marker_failure!(@fail $level, "unimplemented!()",
+            format_args!("not implemented"))
+    );
+    ($level:expr, unimplemented, $($arg:tt)+) => (
+        $crate::__marker_failure!(@fail $level, "unimplemented!()",
+            format_args!("not implemented: {}", format_args!($($arg)+)))
+    );
+    (@fail $level:expr, $expression:expr, $message:expr) => (
+        $crate::__private::fail(&$crate::Failure::__new(
+            Some($level),
+            None,
+            $expression,
+            $crate::__location!(),
+            Some($message),
+        ))
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __marker_at_level {
+    (@site $id:expr, $level:expr, $kind:ident; unchecked $(, $($arg:tt)+)?) => ({
+        if $level > $crate::STATIC_MAX_LEVEL {
+            $crate::__private::unreachable_unchecked()
+        } else if $crate::__site_enabled!($level, concat!(stringify!($kind), "!()"), $id) {
+            $crate::__marker_failure!($level, $kind $(, $($arg)+)?);
+            ::core::panic!(concat!("entered ", stringify!($kind), " code"))
+        } else {
+            ::core::$kind!($($($arg)+)?)
+        }
+    });
+    (@site $id:expr, $level:expr, $kind:ident $(, $config:expr)?; $($($arg:tt)+)?) => (
+        if $crate::__site_enabled!($level, concat!(stringify!($kind), "!()"), $id)
+            $(&& $level <= $config.assertion_level())?
+        {
+            $crate::__marker_failure!($level, $kind $(, $($arg)+)?);
+        }
+    );
+    ($level:expr, $kind:ident; id = $id:literal; unchecked $(, $($arg:tt)+)?) => (
+        $crate::__marker_at_level!(@site Some($id), $level, $kind; unchecked $(, $($arg)+)?)
+    );
+    ($level:expr, $kind:ident; id = $id:literal; $config:expr; $($($arg:tt)+)?) => (
+        $crate::__marker_at_level!(@site Some($id), $level, $kind, $config; $($($arg)+)?)
+    );
+    ($level:expr, $kind:ident; id = $id:literal $(; $($arg:tt)+)?) => (
+        $crate::__marker_at_level!(@site Some($id), $level, $kind; $($($arg)+)?)
+    );
+    ($level:expr, $kind:ident; unchecked $(, $($arg:tt)+)?) => (
+        $crate::__marker_at_level!(@site None, $level, $kind; unchecked $(, $($arg)+)?)
+    );
+    ($level:expr, $kind:ident; $config:expr; $($($arg:tt)+)?) => (
+        $crate::__marker_at_level!(@site None, $level, $kind, $config; $($($arg)+)?)
+    );
+    ($level:expr, $kind:ident; $($($arg:tt)+)?) => (
+        $crate::__marker_at_level!(@site None, $level, $kind; $($($arg)+)?)
+    );
+}
+
+/// Marks code that should be unreachable, checked when Error level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `eunreachable!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unreachable!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`eassert!`](crate::eassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eunreachable;
+/// # fn main() {
+///     let value = 3;
+///     if value > 5 {
+///         eunreachable!("value {} is never larger than 5", value);
+///     }
+///     eunreachable!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eunreachable {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Error, unreachable; $($arg)*));
+}
+
+/// Marks code that should be unreachable, checked when Warn level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `wunreachable!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unreachable!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`wassert!`](crate::wassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wunreachable;
+/// # fn main() {
+///     let value = 3;
+///     if value > 5 {
+///         wunreachable!("value {} is never larger than 5", value);
+///     }
+///     wunreachable!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wunreachable {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Warn, unreachable; $($arg)*));
+}
+
+/// Marks code that should be unreachable, checked when Info level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `iunreachable!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unreachable!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`iassert!`](crate::iassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iunreachable;
+/// # fn main() {
+///     let value = 3;
+///     if value > 5 {
+///         iunreachable!("value {} is never larger than 5", value);
+///     }
+///     iunreachable!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iunreachable {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Info, unreachable; $($arg)*));
+}
+
+/// Marks code that should be unreachable, checked when Debug level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `dunreachable!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unreachable!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`dassert!`](crate::dassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dunreachable;
+/// # fn main() {
+///     let value = 3;
+///     if value > 5 {
+///         dunreachable!("value {} is never larger than 5", value);
+///     }
+///     dunreachable!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dunreachable {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Debug, unreachable; $($arg)*));
+}
+
+/// Marks code that should be unreachable, checked when Trace level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `tunreachable!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unreachable!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`tassert!`](crate::tassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tunreachable;
+/// # fn main() {
+///     let value = 3;
+///     if value > 5 {
+///         tunreachable!("value {} is never larger than 5", value);
+///     }
+///     tunreachable!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tunreachable {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Trace, unreachable; $($arg)*));
+}
+
+/// Marks code that is not implemented yet, checked when Error level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `eunimplemented!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unimplemented!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`eassert!`](crate::eassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::eunimplemented;
+/// # fn main() {
+///     eunimplemented!("shrinking is not supported yet");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! eunimplemented {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Error, unimplemented; $($arg)*));
+}
+
+/// Marks code that is not implemented yet, checked when Warn level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `wunimplemented!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unimplemented!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`wassert!`](crate::wassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::wunimplemented;
+/// # fn main() {
+///     wunimplemented!("shrinking is not supported yet");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wunimplemented {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Warn, unimplemented; $($arg)*));
+}
+
+/// Marks code that is not implemented yet, checked when Info level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `iunimplemented!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unimplemented!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`iassert!`](crate::iassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::iunimplemented;
+/// # fn main() {
+///     iunimplemented!("shrinking is not supported yet");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iunimplemented {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Info, unimplemented; $($arg)*));
+}
+
+/// Marks code that is not implemented yet, checked when Debug level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `dunimplemented!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unimplemented!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`dassert!`](crate::dassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::dunimplemented;
+/// # fn main() {
+///     dunimplemented!("shrinking is not supported yet");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dunimplemented {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Debug, unimplemented; $($arg)*));
+}
+
+/// Marks code that is not implemented yet, checked when Trace level assertions are enabled.
+///
+/// When the level is disabled this does nothing, and execution continues after it.
+///
+/// The `unchecked` form, `tunimplemented!(unchecked, ...)`, must be wrapped in `unsafe`. When the
+/// level is compiled out by [`STATIC_MAX_LEVEL`](crate::STATIC_MAX_LEVEL), it calls
+/// [`std::hint::unreachable_unchecked`]. Otherwise it panics, after calling the failure handler
+/// when the level is enabled, and like [`unimplemented!`] when it is disabled at runtime, so it can
+/// be used where a value is expected. See [`tassert!`](crate::tassert) for when the level is
+/// enabled.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use invariants::tunimplemented;
+/// # fn main() {
+///     tunimplemented!("shrinking is not supported yet");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tunimplemented {
+    ($($arg:tt)*) => ($crate::__marker_at_level!($crate::AssertLevel::Trace, unimplemented; $($arg)*));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::level_lock;
+    use crate::{AssertConfig, AssertLevel};
+
+    fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let payload = std::panic::catch_unwind(f).unwrap_err();
+        match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn enabled_markers_fail() {
+        let _lock = level_lock();
+        assert_eq!(
+            panic_message(|| tunreachable!()),
+            "internal error: entered unreachable code"
+        );
+        assert_eq!(
+            panic_message(|| dunimplemented!("{} rows", 3)),
+            "not implemented: 3 rows"
+        );
+    }
+
+    #[test]
+    fn disabled_markers_do_nothing() {
+        let _lock = level_lock();
+        iunreachable!(AssertConfig::new(AssertLevel::Warn););
+        let _guard = crate::scoped_thread_level(AssertLevel::Warn);
+        tunreachable!("not checked");
+        iunimplemented!();
+        assert!(std::panic::catch_unwind(|| wunimplemented!()).is_err());
+    }
+
+    #[test]
+    fn unchecked_marker_diverges_when_enabled() {
+        let _lock = level_lock();
+        let value: Option<u32> = None;
+        let result = std::panic::catch_unwind(|| match value {
+            Some(value) => value,
+            None => unsafe { eunreachable!(unchecked, "value is always set") },
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unchecked_marker_panics_when_disabled_at_runtime() {
+        let _lock = level_lock();
+        let _guard = crate::scoped_thread_level(AssertLevel::Off);
+        assert_eq!(
+            panic_message(|| unsafe { tunimplemented!(unchecked, "{} rows", 3) }),
+            "not implemented: 3 rows"
+        );
+        crate::set_max_level(AssertLevel::Off);
+        let value: Option<u32> = None;
+        let result = std::panic::catch_unwind(|| match value {
+            Some(value) => value,
+            None => unsafe { eunreachable!(unchecked) },
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Non-panicking leveled checks.
+//!
+//! The `echeck!`..`tcheck!` macros evaluate the same way as the assertion macros, but return an
+//! [`InvariantViolation`] instead of calling the failure handler, so they can be used with `?`
+//! in code that must not panic. They accept the same optional `id = "name";` and `config;`
+//! prefixes.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{AssertLevel, Failure, Location};
+
+/// A violated invariant, returned by the leveled check macros like [`tcheck!`](crate::tcheck).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    level: Option<AssertLevel>,
+    expression: &'static str,
+    location: Location,
+    message: String,
+}
+
+impl InvariantViolation {
+    /// Returns the level of the violated check, if it had one.
+    pub fn level(&self) -> Option<AssertLevel> {
+        self.level
+    }
+
+    /// Returns the text of the checked expression.
+    pub fn expression(&self) -> &'static str {
+        self.expression
+    }
+
+    /// Returns the location of the check.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Returns the failure message, formatted like the panic message of the matching assertion.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<&Failure<'_>> for InvariantViolation {
+    fn from(failure: &Failure<'_>) -> Self {
+        Self {
+            level: failure.level(),
+            expression: failure.expression(),
+            location: *failure.location(),
+            message: failure.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.location)
+    }
+}
+
+impl Error for InvariantViolation {}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __violation {
+    (@text $text:expr, $level:expr; $cond:expr $(, $($arg:tt)*)?) => (
+        if $cond {
+            ::core::result::Result::<(), $crate::InvariantViolation>::Ok(())
+        } else {
+            ::core::result::Result::Err($crate::InvariantViolation::from(&$crate::Failure::__new(
+                Some($level),
+                None,
+                $text,
+                $crate::__location!(),
+                $crate::__message!($($($arg)*)?),
+            )))
+        }
+    );
+    ($level:expr; $cond:expr $(, $($arg:tt)*)?) => (
+        $crate::__violation!(@text stringify!($cond), $level; $cond $(, $($arg)*)?)
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_at_level {
+    // The condition's text is given, for conditions generated by the derive.
+    (@text $text:expr, $level:expr, $config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!(@site None, $text, $level, $config; $($arg)*)
+    );
+    (@site $id:expr, $text:expr, $level:expr $(, $config:expr)?; $($arg:tt)*) => (
+        if $crate::__site_enabled!($level, $text, $id)
+            $(&& $level <= $config.assertion_level())?
+        {
+            $crate::__violation!(@text $text, $level; $($arg)*)
+        } else {
+            ::core::result::Result::<(), $crate::InvariantViolation>::Ok(())
+        }
+    );
+    ($level:expr; id = $id:literal; $config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!(
+            @site Some($id), $crate::__condition!($($arg)*), $level, $config; $($arg)*
+        )
+    );
+    ($level:expr; id = $id:literal; $($arg:tt)*) => (
+        $crate::__check_at_level!(@site Some($id), $crate::__condition!($($arg)*), $level; $($arg)*)
+    );
+    ($level:expr, $config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!(
+            @site None, $crate::__condition!($($arg)*), $level, $config; $($arg)*
+        )
+    );
+    ($level:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!(@site None, $crate::__condition!($($arg)*), $level; $($arg)*)
+    );
+}
+
+/// Checks that the given expression is true when Error level assertions are enabled, without
+/// panicking.
+///
+/// Evaluates to `Err(InvariantViolation)` if the level is enabled and the expression is false,
+/// and to `Ok(())` otherwise. See [`eassert!`](crate::eassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{echeck, InvariantViolation};
+///
+/// fn pop(stack: &mut Vec<u32>) -> Result<u32, InvariantViolation> {
+///     echeck!(!stack.is_empty(), "pop from an empty stack")?;
+///     Ok(stack.pop().unwrap_or_default())
+/// }
+///
+/// # fn main() {
+///     assert!(pop(&mut vec![]).is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! echeck {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Error; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Error, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__check_at_level!($crate::AssertLevel::Error; $($arg)*));
+}
+
+/// Checks that the given expression is true when Warn level assertions are enabled, without
+/// panicking.
+///
+/// Evaluates to `Err(InvariantViolation)` if the level is enabled and the expression is false,
+/// and to `Ok(())` otherwise. See [`wassert!`](crate::wassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{wcheck, InvariantViolation};
+///
+/// fn pop(stack: &mut Vec<u32>) -> Result<u32, InvariantViolation> {
+///     wcheck!(!stack.is_empty(), "pop from an empty stack")?;
+///     Ok(stack.pop().unwrap_or_default())
+/// }
+///
+/// # fn main() {
+///     assert!(pop(&mut vec![]).is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wcheck {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Warn; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Warn, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__check_at_level!($crate::AssertLevel::Warn; $($arg)*));
+}
+
+/// Checks that the given expression is true when Info level assertions are enabled, without
+/// panicking.
+///
+/// Evaluates to `Err(InvariantViolation)` if the level is enabled and the expression is false,
+/// and to `Ok(())` otherwise. See [`iassert!`](crate::iassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{icheck, InvariantViolation};
+///
+/// fn pop(stack: &mut Vec<u32>) -> Result<u32, InvariantViolation> {
+///     icheck!(!stack.is_empty(), "pop from an empty stack")?;
+///     Ok(stack.pop().unwrap_or_default())
+/// }
+///
+/// # fn main() {
+///     assert!(pop(&mut vec![]).is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! icheck {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Info; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Info, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__check_at_level!($crate::AssertLevel::Info; $($arg)*));
+}
+
+/// Checks that the given expression is true when Debug level assertions are enabled, without
+/// panicking.
+///
+/// Evaluates to `Err(InvariantViolation)` if the level is enabled and the expression is false,
+/// and to `Ok(())` otherwise. See [`dassert!`](crate::dassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{dcheck, InvariantViolation};
+///
+/// fn pop(stack: &mut Vec<u32>) -> Result<u32, InvariantViolation> {
+///     dcheck!(!stack.is_empty(), "pop from an empty stack")?;
+///     Ok(stack.pop().unwrap_or_default())
+/// }
+///
+/// # fn main() {
+///     assert!(pop(&mut vec![]).is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dcheck {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Debug; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Debug, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__check_at_level!($crate::AssertLevel::Debug; $($arg)*));
+}
+
+/// Checks that the given expression is true when Trace level assertions are enabled, without
+/// panicking.
+///
+/// Evaluates to `Err(InvariantViolation)` if the level is enabled and the expression is false,
+/// and to `Ok(())` otherwise. See [`tassert!`](crate::tassert) for when the level is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use invariants::{tcheck, InvariantViolation};
+///
+/// fn pop(stack: &mut Vec<u32>) -> Result<u32, InvariantViolation> {
+///     tcheck!(!stack.is_empty(), "pop from an empty stack")?;
+///     Ok(stack.pop().unwrap_or_default())
+/// }
+///
+/// # fn main() {
+///     assert!(pop(&mut vec![]).is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tcheck {
+    (id = $id:literal; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Trace; id = $id; $($arg)*)
+    );
+    ($config:expr; $($arg:tt)*) => (
+        $crate::__check_at_level!($crate::AssertLevel::Trace, $config; $($arg)*)
+    );
+    ($($arg:tt)*) => ($crate::__check_at_level!($crate::AssertLevel::Trace; $($arg)*));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::level_lock;
+    use crate::AssertConfig;
+
+    fn checked(value: u32) -> Result<u32, InvariantViolation> {
+        dcheck!(value < 10, "{} is too large", value)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn check_returns_violation() {
+        let _lock = level_lock();
+        assert_eq!(checked(3), Ok(3));
+        let violation = checked(12).unwrap_err();
+        assert_eq!(violation.level(), Some(AssertLevel::Debug));
+        assert_eq!(violation.expression(), "value < 10");
+        assert_eq!(violation.message(), "12 is too large");
+        assert_eq!(violation.location().module_path(), module_path!());
+        assert_eq!(
+            violation.to_string(),
+            format!("12 is too large at {}", violation.location())
+        );
+    }
+
+    #[test]
+    fn disabled_check_is_ok() {
+        let _lock = level_lock();
+        assert!(echeck!(1 + 1 == 3,).is_err());
+        assert!(tcheck!(AssertConfig::new(AssertLevel::Info); 1 + 1 == 3).is_ok());
+        let _guard = crate::scoped_level(AssertLevel::Warn);
+        assert!(icheck!(1 + 1 == 3).is_ok());
+        assert!(wcheck!(1 + 1 == 3).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "macros")]
+
+use invariants::{ensures, requires, set_site_enabled, sites, AssertConfig, AssertLevel};
+
+struct Stack {
+    items: Vec<u32>,
+}
+
+impl Stack {
+    #[requires(level = debug, idx < self.items.len())]
+    fn get(&self, idx: usize) -> u32 {
+        self.items[idx]
+    }
+
+    #[ensures(level = trace, ret.is_some() -> self.items.len() < 3)]
+    fn pop(&mut self) -> Option<u32> {
+        let top = self.items.pop()?;
+        if top == 0 {
+            return None;
+        }
+        Some(top)
+    }
+
+    #[requires(level = trace, config = AssertConfig::new(AssertLevel::Off), !self.items.is_empty())]
+    fn top(&self) -> Option<&u32> {
+        self.items.last()
+    }
+}
+
+#[ensures(ret >= a && ret >= b)]
+#[ensures(ret == a || ret == b)]
+fn max(a: u32, b: u32) -> u32 {
+    if a > b {
+        a
+    } else {
+        if b > 10 {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+#[requires(items.iter().all(|x| -> bool { *x > 0 }))]
+#[ensures(!items.is_empty() -> items.iter().any(|&x| -> bool { x == ret }))]
+fn smallest(items: &[u32]) -> u32 {
+    items.iter().copied().min().unwrap_or(0)
+}
+
+#[test]
+fn contracts_hold() {
+    let mut stack = Stack {
+        items: vec![0, 1, 2],
+    };
+    assert_eq!(stack.get(1), 1);
+    assert_eq!(stack.pop(), Some(2));
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.pop(), None);
+    assert_eq!(stack.top(), None);
+    assert_eq!(max(3, 5), 5);
+    assert_eq!(smallest(&[3, 1, 2]), 1);
+    assert_eq!(smallest(&[]), 0);
+}
+
+#[test]
+#[should_panic(
+    expected = "precondition `items.iter().all(|x| -> bool { *x > 0 })` of `smallest` violated"
+)]
+fn closure_with_return_type_in_contract() {
+    smallest(&[1, 0]);
+}
+
+#[test]
+#[should_panic(expected = "precondition `idx < self.items.len()` of `get` violated")]
+fn precondition_violated() {
+    let stack = Stack { items: vec![1] };
+    stack.get(1);
+}
+
+#[test]
+#[should_panic(
+    expected = "postcondition `ret.is_some() -> self.items.len() < 3` of `pop` violated"
+)]
+fn postcondition_violated() {
+    let mut stack = Stack {
+        items: vec![1, 2, 3, 4],
+    };
+    stack.pop();
+}
+
+#[test]
+#[should_panic(expected = "postcondition `ret >= a && ret >= b` of `max` violated")]
+fn stacked_postconditions() {
+    max(5, 11);
+}
+
+struct Counter {
+    items: Vec<u32>,
+}
+
+impl Counter {
+    #[ensures(self.items.len() == old(self.items.len()) + 1)]
+    #[ensures(level = trace, ret == old(self.items.clone()).into_iter().sum::<u32>() + x)]
+    fn push(&mut self, x: u32) -> u32 {
+        self.items.push(x);
+        self.items.iter().sum()
+    }
+
+    #[ensures(self.items.len() == old(self.items.len()) + 1)]
+    fn push_twice(&mut self, x: u32) {
+        self.items.push(x);
+        self.items.push(x);
+    }
+}
+
+#[test]
+fn old_values_are_captured() {
+    let mut counter = Counter { items: vec![1] };
+    assert_eq!(counter.push(2), 3);
+    assert_eq!(counter.items, [1, 2]);
+}
+
+#[test]
+#[should_panic(
+    expected = "postcondition `self.items.len() == old(self.items.len()) + 1` of `push_twice` violated"
+)]
+fn old_value_postcondition_violated() {
+    let mut counter = Counter { items: vec![] };
+    counter.push_twice(1);
+}
+
+#[derive(PartialEq)]
+struct NoClone(u32);
+
+impl Clone for NoClone {
+    fn clone(&self) -> Self {
+        panic!("cloned a disabled old value")
+    }
+}
+
+#[ensures(config = AssertConfig::new(AssertLevel::Off), value.0 == old(value.0) + 1)]
+#[ensures(level = debug, config = AssertConfig::new(AssertLevel::Info), *value != old(NoClone(0)))]
+fn increment(value: &mut NoClone) {
+    value.0 += 1;
+}
+
+#[test]
+fn old_values_are_not_captured_when_disabled() {
+    let mut value = NoClone(1);
+    increment(&mut value);
+    assert_eq!(value.0, 2);
+}
+
+/// The line of the postcondition of `decrement`.
+const DECREMENT_LINE: u32 = line!() + 2;
+
+#[ensures(level = debug, *value != old(NoClone(0)))]
+fn decrement(value: &mut NoClone) {
+    value.0 -= 1;
+}
+
+#[test]
+fn old_values_are_not_captured_when_the_site_is_off() {
+    set_site_enabled(&format!("contracts.rs:{}", DECREMENT_LINE), false);
+    let mut value = NoClone(1);
+    decrement(&mut value);
+    assert_eq!(value.0, 0);
+    let sites: Vec<_> = sites()
+        .filter(|site| site.location().line() == DECREMENT_LINE)
+        .map(|site| (site.level(), site.condition()))
+        .collect();
+    assert_eq!(
+        sites,
+        [(Some(AssertLevel::Debug), "*value != old(NoClone(0))")]
+    );
+}
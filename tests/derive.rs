@@ -0,0 +1,131 @@
+#![cfg(feature = "macros")]
+
+use invariants::{sites, tassert_invariant, with_thread_level, AssertLevel, Invariant};
+
+#[derive(Invariant)]
+struct Histogram {
+    #[inv(error, non_empty)]
+    #[inv(trace, sorted)]
+    bounds: Vec<u32>,
+    #[inv(debug, range = 0..100)]
+    percentile: u8,
+}
+
+#[derive(Invariant)]
+struct Report<T> {
+    #[inv(debug, nested)]
+    histogram: Histogram,
+    #[inv(info, nested)]
+    extra: T,
+}
+
+#[derive(Invariant)]
+struct Name(#[inv(info, non_empty)] String);
+
+fn histogram(bounds: Vec<u32>, percentile: u8) -> Histogram {
+    Histogram { bounds, percentile }
+}
+
+#[test]
+fn constraints_hold() {
+    assert!(histogram(vec![1, 10, 100], 50).holds());
+    assert!(Name("a".to_string()).holds());
+    let report = Report {
+        histogram: histogram(vec![1], 0),
+        extra: Name("a".to_string()),
+    };
+    assert_eq!(report.check_invariant(AssertLevel::Trace), Ok(()));
+}
+
+#[test]
+fn constraints_are_violated() {
+    let violation = histogram(vec![], 50)
+        .check_invariant(AssertLevel::Error)
+        .unwrap_err();
+    assert_eq!(violation.level(), Some(AssertLevel::Error));
+    assert_eq!(violation.expression(), "!self.bounds.is_empty()");
+    assert_eq!(
+        violation.message(),
+        "field `bounds` of `Histogram` is empty"
+    );
+
+    let violation = histogram(vec![1], 100)
+        .check_invariant(AssertLevel::Trace)
+        .unwrap_err();
+    assert_eq!(
+        violation.expression(),
+        "(0..100).contains(&self.percentile)"
+    );
+    assert_eq!(
+        violation.message(),
+        "field `percentile` of `Histogram` is not in range `0..100`"
+    );
+
+    let violation = Name(String::new())
+        .check_invariant(AssertLevel::Info)
+        .unwrap_err();
+    assert_eq!(violation.message(), "field `0` of `Name` is empty");
+}
+
+#[test]
+fn constraints_above_the_level_are_skipped() {
+    let unsorted = histogram(vec![2, 1], 50);
+    assert!(!unsorted.holds());
+    assert_eq!(
+        unsorted
+            .check_invariant(AssertLevel::Trace)
+            .unwrap_err()
+            .expression(),
+        "is_sorted(&self.bounds)"
+    );
+    assert_eq!(unsorted.check_invariant(AssertLevel::Debug), Ok(()));
+
+    let report = Report {
+        histogram: histogram(vec![], 0),
+        extra: Name(String::new()),
+    };
+    assert_eq!(report.check_invariant(AssertLevel::Off), Ok(()));
+    assert_eq!(
+        report
+            .check_invariant(AssertLevel::Info)
+            .unwrap_err()
+            .message(),
+        "field `0` of `Name` is empty"
+    );
+    assert_eq!(
+        report
+            .check_invariant(AssertLevel::Debug)
+            .unwrap_err()
+            .message(),
+        "field `bounds` of `Histogram` is empty"
+    );
+}
+
+/// The constraints are gated like the `tcheck!` macros, and are call sites of their own.
+#[test]
+fn constraints_are_call_sites() {
+    let empty = Name(String::new());
+    assert!(with_thread_level(AssertLevel::Warn, || empty.holds()));
+    assert!(!empty.holds());
+    let conditions: Vec<_> = sites()
+        .filter(|site| site.location().module_path() == module_path!())
+        .map(|site| (site.level(), site.condition()))
+        .filter(|(_, condition)| condition.contains("self.0"))
+        .collect();
+    assert_eq!(
+        conditions,
+        [(Some(AssertLevel::Info), "!self.0.is_empty()")]
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "invariant of `reports` violated: field `percentile` of `Histogram` is not in range `0..100`"
+)]
+fn nested_containers_are_checked() {
+    let reports = vec![Report {
+        histogram: histogram(vec![1], 100),
+        extra: Some(Name("a".to_string())),
+    }];
+    tassert_invariant!(reports);
+}
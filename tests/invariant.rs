@@ -0,0 +1,106 @@
+#![cfg(feature = "macros")]
+
+use std::cell::{Ref, RefCell};
+
+use invariants::invariant;
+
+#[derive(Debug, Default)]
+struct UniqueVec {
+    items: Vec<u32>,
+}
+
+#[invariant(level = "trace", check = Self::well_formed)]
+impl UniqueVec {
+    fn well_formed(&self) -> bool {
+        self.items
+            .iter()
+            .enumerate()
+            .all(|(i, x)| !self.items[..i].contains(x))
+    }
+
+    pub fn insert(&mut self, x: u32) -> bool {
+        if self.items.contains(&x) {
+            return false;
+        }
+        self.items.push(x);
+        true
+    }
+
+    pub fn insert_unchecked(&mut self, x: u32) {
+        self.items.push(x);
+    }
+
+    pub fn try_remove(&mut self, x: u32) -> Result<u32, String> {
+        let index = self.items.iter().position(|&y| y == x).ok_or("missing")?;
+        Ok(self.items.remove(index))
+    }
+
+    pub fn first_mut(&mut self) -> Option<&mut u32> {
+        self.items.first_mut()
+    }
+
+    pub fn push_named(&mut self, x: u32) -> &'static str {
+        self.items.push(x);
+        "pushed"
+    }
+
+    #[invariant(skip)]
+    pub fn clear_and_corrupt(&mut self) {
+        self.items = vec![1, 1];
+    }
+}
+
+#[test]
+fn invariant_holds() {
+    let mut set = UniqueVec::default();
+    assert!(set.insert(1));
+    assert!(!set.insert(1));
+    assert_eq!(set.try_remove(1), Ok(1));
+    assert_eq!(set.try_remove(1), Err("missing".to_string()));
+    assert!(set.first_mut().is_none());
+}
+
+#[test]
+#[should_panic(expected = "invariant `Self::well_formed` violated on exit from `insert_unchecked`")]
+fn invariant_checked_on_exit() {
+    let mut set = UniqueVec::default();
+    set.insert(1);
+    set.insert_unchecked(1);
+}
+
+/// `'static` borrows don't keep `push_named` from checking the invariant on exit.
+#[test]
+#[should_panic(expected = "invariant `Self::well_formed` violated on exit from `push_named`")]
+fn static_borrow_checked_on_exit() {
+    let mut set = UniqueVec::default();
+    assert_eq!(set.push_named(1), "pushed");
+    set.push_named(1);
+}
+
+struct Shared {
+    items: RefCell<Vec<u32>>,
+}
+
+#[invariant(check = |shared: &Self| shared.items.try_borrow_mut().is_ok())]
+impl Shared {
+    /// The lifetime is written out, so the result is known to borrow `self`.
+    pub fn items(&mut self) -> Ref<'_, Vec<u32>> {
+        self.items.borrow()
+    }
+}
+
+#[test]
+fn elided_path_lifetime_written_out_is_checked_on_entry() {
+    let mut shared = Shared {
+        items: RefCell::new(vec![1]),
+    };
+    assert_eq!(*shared.items(), [1]);
+}
+
+#[test]
+#[should_panic(expected = "violated on entry to `insert`")]
+fn invariant_checked_on_entry() {
+    let mut set = UniqueVec::default();
+    set.clear_and_corrupt();
+    set.insert(2);
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "macros")]
+
+use invariants::{loop_invariant, loop_invariants};
+
+#[loop_invariants]
+fn binary_search(items: &[u32], x: u32) -> Option<usize> {
+    let (mut lo, mut hi) = (0, items.len());
+    #[loop_invariant(trace, lo <= hi && hi <= items.len())]
+    #[loop_invariant(debug, items[..lo].iter().all(|&y| y < x))]
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match items[mid].cmp(&x) {
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+            std::cmp::Ordering::Equal => return Some(mid),
+        }
+    }
+    None
+}
+
+#[loop_invariants]
+fn partition(items: &mut [u32], pivot: u32, broken_at: Option<usize>) -> usize {
+    let mut split = 0;
+    for i in 0..items.len() {
+        loop_invariant!(split <= items.len());
+        loop_invariant!(info; items[..split].iter().all(|&x| x < pivot), "split is {}", split);
+        if items[i] < pivot || Some(i) == broken_at {
+            items.swap(i, split);
+            split += 1;
+            continue;
+        }
+    }
+    split
+}
+
+/// The line of the invariant in `count`.
+const COUNT_INVARIANT_LINE: u32 = line!() + 5;
+
+#[loop_invariants]
+fn count(from: u32, to: u32, limit: u32) -> u32 {
+    let mut count = from;
+    #[loop_invariant(count <= limit)]
+    loop {
+        count += 1;
+        if count >= to {
+            break count;
+        }
+    }
+}
+
+// Without a `break`, the loop never exits, so there are no checks after it.
+#[deny(unreachable_code)]
+#[loop_invariants]
+fn find_zero(items: &[u32]) -> Option<usize> {
+    let mut i = 0;
+    loop {
+        loop_invariant!(i <= items.len());
+        if i == items.len() {
+            return None;
+        }
+        if items[i] == 0 {
+            return Some(i);
+        }
+        i += 1;
+    }
+}
+
+#[test]
+fn loop_invariants_hold() {
+    let items = [1, 3, 5, 7];
+    assert_eq!(binary_search(&items, 5), Some(2));
+    assert_eq!(binary_search(&items, 4), None);
+    assert_eq!(binary_search(&[], 4), None);
+    let mut items = [5, 1, 4, 2];
+    assert_eq!(partition(&mut items, 3, None), 2);
+    assert_eq!(count(0, 3, 3), 3);
+    assert_eq!(find_zero(&[2, 0, 1]), Some(1));
+    assert_eq!(find_zero(&[2]), None);
+}
+
+#[test]
+fn checks_are_located_at_the_invariant() {
+    count(0, 2, 2);
+    let lines: Vec<u32> = invariants::sites()
+        .filter(|site| site.condition() == "count <= limit")
+        .map(|site| site.location().line())
+        .collect();
+    assert_eq!(lines, [COUNT_INVARIANT_LINE; 3]);
+}
+
+#[test]
+#[should_panic(
+    expected = "loop invariant `items[..split].iter().all(|&x| x < pivot)` violated after iteration 2: split is 2"
+)]
+fn violated_after_iteration() {
+    let mut items = [1, 5, 4, 2];
+    partition(&mut items, 3, Some(1));
+}
+
+#[test]
+#[should_panic(expected = "loop invariant `count <= limit` violated on entry")]
+fn violated_on_entry() {
+    count(5, 6, 4);
+}
+
+#[test]
+#[should_panic(expected = "loop invariant `count <= limit` violated on exit after 3 iterations")]
+fn violated_on_exit() {
+    count(0, 3, 2);
+}
@@ -0,0 +1,173 @@
+#![cfg(feature = "macros")]
+
+use invariants::{trait_invariant, AssertConfig, AssertLevel};
+
+#[trait_invariant(debug, self.len() == self.iter().count())]
+#[trait_invariant(trace, config = AssertConfig::new(AssertLevel::Debug), self.len() < 100)]
+trait Collection {
+    fn len(&self) -> usize;
+    fn iter(&self) -> std::slice::Iter<'_, u32>;
+    fn push(&mut self, x: u32);
+    fn last_mut(&mut self) -> Option<&mut u32>;
+    #[trait_invariant(skip)]
+    fn clear(&mut self);
+}
+
+struct Stack(Vec<u32>);
+
+#[trait_invariant]
+impl Collection for Stack {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, u32> {
+        self.0.iter()
+    }
+
+    fn push(&mut self, x: u32) {
+        self.0.push(x);
+    }
+
+    fn last_mut(&mut self) -> Option<&mut u32> {
+        self.0.last_mut()
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Caches its length, and forgets to update it.
+struct CachedStack {
+    items: Vec<u32>,
+    len: usize,
+}
+
+#[trait_invariant]
+impl Collection for CachedStack {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, u32> {
+        self.items.iter()
+    }
+
+    fn push(&mut self, x: u32) {
+        self.items.push(x);
+    }
+
+    fn last_mut(&mut self) -> Option<&mut u32> {
+        self.items.last_mut()
+    }
+
+    #[trait_invariant(skip)]
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+/// The `self.len() < 100` invariant is disabled by its config.
+#[test]
+fn trait_invariants_hold() {
+    let mut stack = Stack(Vec::new());
+    for x in 0..200 {
+        stack.push(x);
+    }
+    assert_eq!(stack.last_mut(), Some(&mut 199));
+    let mut cached = CachedStack {
+        items: vec![],
+        len: 0,
+    };
+    assert_eq!(cached.last_mut(), None);
+    cached.items.push(1);
+    cached.clear();
+}
+
+#[test]
+#[should_panic(
+    expected = "trait invariant `self.len() == self.iter().count()` of `Collection` violated on exit from `push`"
+)]
+fn trait_invariant_violated() {
+    let mut cached = CachedStack {
+        items: vec![],
+        len: 0,
+    };
+    cached.push(1);
+}
+
+#[trait_invariant(self.total() <= self.limit())]
+trait Budget<T: Copy + Into<u64>> {
+    fn total(&self) -> u64;
+    fn limit(&self) -> u64;
+    fn spend(&mut self, amount: T);
+    fn spend_all<I: IntoIterator<Item = T>>(&mut self, (items, _note): (I, &str)) {
+        for amount in items {
+            self.spend(amount);
+        }
+    }
+    /// # Safety
+    ///
+    /// The amount must not exceed the budget.
+    unsafe fn spend_unchecked(&mut self, amount: T);
+    fn reset(&mut self) -> u64 {
+        let total = self.total();
+        self.spend_unchecked_total(0);
+        total
+    }
+    fn spend_unchecked_total(&mut self, total: u64);
+    fn spend_each(&mut self, amounts: impl IntoIterator<Item = T>) {
+        for amount in amounts {
+            self.spend(amount);
+        }
+    }
+}
+
+struct Wallet {
+    total: u64,
+}
+
+impl Budget<u32> for Wallet {
+    fn total(&self) -> u64 {
+        self.total
+    }
+
+    fn limit(&self) -> u64 {
+        10
+    }
+
+    fn spend(&mut self, amount: u32) {
+        self.total += u64::from(amount);
+    }
+
+    unsafe fn spend_unchecked(&mut self, amount: u32) {
+        self.total += u64::from(amount);
+    }
+
+    fn spend_unchecked_total(&mut self, total: u64) {
+        self.total = total;
+    }
+}
+
+/// `Wallet` is not marked with `#[trait_invariant]`, so only the `checked_` calls are checked.
+#[test]
+fn generic_trait_invariants_hold() {
+    let mut wallet = Wallet { total: 0 };
+    wallet.checked_spend(3);
+    wallet.checked_spend_all((vec![1, 2], "groceries"));
+    unsafe { wallet.checked_spend_unchecked(4) };
+    assert_eq!(wallet.checked_reset(), 10);
+    wallet.checked_spend_each([1u32, 2]);
+    assert_eq!(wallet.total, 3);
+    wallet.spend(20);
+}
+
+#[test]
+#[should_panic(
+    expected = "trait invariant `self.total() <= self.limit()` of `Budget` violated on exit from `spend_all`"
+)]
+fn generic_trait_invariant_violated() {
+    let mut wallet = Wallet { total: 0 };
+    wallet.checked_spend_all((vec![5, 6], "rent"));
+}